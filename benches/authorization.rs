@@ -0,0 +1,79 @@
+//! Compare the string-based authorization functions with a compiled
+//! `ScopeSet` for a service account holding a few hundred scopes.
+//!
+//! Run with `cargo bench --bench authorization`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use usso::authorization::{check_access, get_scope_filters, has_subset_scope, Action, ScopeSet};
+
+const ACTIONS: [&str; 4] = ["read", "update", "delete", "admin"];
+
+fn service_account_scopes() -> Vec<String> {
+    (0..300)
+        .map(|i| {
+            format!(
+                "{}:service-{}/resource-{}?tenant_id=t{}",
+                ACTIONS[i % ACTIONS.len()],
+                i / 10,
+                i,
+                i % 7
+            )
+        })
+        .collect()
+}
+
+fn bench_authorization(c: &mut Criterion) {
+    let scopes = service_account_scopes();
+    let set = ScopeSet::compile(&scopes).unwrap();
+    let filters = HashMap::from([("tenant_id".to_string(), "t4".to_string())]);
+    let subset = "read:service-25/resource-251?tenant_id=t6";
+    let parsed_subset = subset.parse().unwrap();
+
+    let mut group = c.benchmark_group("check_access");
+    group.bench_function("strings", |b| {
+        b.iter(|| {
+            check_access(
+                black_box(&scopes),
+                black_box("service-29/resource-298"),
+                Some(Action::Update),
+                Some(&filters),
+                false,
+            )
+        })
+    });
+    group.bench_function("scope_set", |b| {
+        b.iter(|| {
+            black_box(&set).check_access(
+                black_box("service-29/resource-298"),
+                Some(Action::Update.as_str()),
+                Some(&filters),
+                false,
+            )
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("get_scope_filters");
+    group.bench_function("strings", |b| {
+        b.iter(|| get_scope_filters(Action::Read, black_box("service-12/resource-120"), &scopes))
+    });
+    group.bench_function("scope_set", |b| {
+        b.iter(|| black_box(&set).get_scope_filters("read", black_box("service-12/resource-120")))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("has_subset_scope");
+    group.bench_function("strings", |b| {
+        b.iter(|| has_subset_scope(black_box(subset), &scopes))
+    });
+    group.bench_function("scope_set", |b| {
+        b.iter(|| black_box(&set).has_subset_scope(black_box(&parsed_subset)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_authorization);
+criterion_main!(benches);
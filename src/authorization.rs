@@ -16,19 +16,41 @@
 //! | `owner` / `*` | 90 |
 //! | `superadmin` | 100 |
 //!
+//! This is the default [`ActionRegistry`], used by the functions of this
+//! module. To add verbs such as `approve` or `export`, order them by level or
+//! through explicit implications, or decide what happens to unknown verbs,
+//! build your own registry and pass it explicitly: its methods mirror the
+//! functions ([`ActionRegistry::check_access`], ...) with actions given by
+//! name, and [`ScopeSet::compile_with`] compiles scopes against it.
+//!
 //! Wildcards (`*`) in path segments and filter values match any value.
 //!
+//! # Deny scopes
+//!
+//! A scope prefixed with `!` or `deny:` (`!delete:billing/invoices`,
+//! `deny:delete:billing/invoices`) takes access away, and overrides every
+//! grant: it denies its action and every action implying it on matching paths, so
+//! `!read:billing` denies all access to `billing`. A deny with filters also
+//! applies to requests that carry no filters. Deny scopes never authorize
+//! anything by themselves, and [`has_subset_scope`] refuses to delegate any
+//! scope that overlaps one of the user's denies.
+//!
 //! # Types
 //!
 //! | Type | Description |
 //! |------|-------------|
 //! | [`Action`] | Enum for the 9 known privilege levels (Read, Write, Admin, etc.) |
+//! | [`ActionRegistry`] | Levels and implications between actions |
+//! | [`Scope`] | A validated, pre-parsed scope (`FromStr`, `Display`, serde as a string) |
+//! | [`ScopeSet`] | Scopes compiled once into a path index for hot-path checks |
+//! | [`AccessDecision`] | Serializable explanation of an access check |
 //!
 //! # Public functions
 //!
 //! | Function | Description |
 //! |----------|-------------|
 //! | [`parse_scope`] | Parse a scope string into (action, path, filters) |
+//! | [`is_deny_scope`] | Check if a scope string is a deny scope |
 //! | [`check_access`] | Check if any scope grants access to a resource |
 //! | [`is_authorized`] | Check if a single scope grants access |
 //! | [`has_subset_scope`] | Check if any scope can delegate a subset scope |
@@ -39,6 +61,14 @@
 //! | [`broadest_scope_filter`] | Return the least restrictive filter from a list |
 //! | [`owner_authorization`] | Owner-level authorization check against user/owner/workspace IDs |
 //! | [`get_common_scopes`] | Intersection of two scope lists |
+//! | [`check_access_scopes`] | [`check_access`] over pre-parsed [`Scope`]s |
+//! | [`explain_access`] | [`check_access`] with an [`AccessDecision`] explaining the result |
+//!
+//! The string-based functions parse leniently: unknown actions count as
+//! [`unknown_action`](ActionRegistry::unknown_action) (`read` by default) and
+//! malformed filters are dropped. Parse scopes into [`Scope`]s
+//! once to reject those with a [`ScopeParseError`] instead, and to avoid
+//! re-parsing on every check ([`Scope::is_authorized`], [`Scope::is_subset_of`]).
 //!
 //! # Example
 //!
@@ -48,15 +78,26 @@
 //! let scopes = vec!["admin:users".into(), "read:reports".into()];
 //! assert!(check_access(&scopes, "users", Some(Action::Delete), None, false));
 //! assert!(!check_access(&scopes, "billing", Some(Action::Read), None, false));
+//!
+//! let scopes = vec!["admin:billing/*".into(), "!delete:billing/invoices".into()];
+//! assert!(check_access(&scopes, "billing/invoices", Some(Action::Update), None, false));
+//! assert!(!check_access(&scopes, "billing/invoices", Some(Action::Delete), None, false));
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exceptions::ScopeParseError;
+use crate::schemas::UserData;
 
 /// A known USSO action/privilege level.
 ///
-/// Each variant maps to a numeric level used in the hierarchical RBAC engine:
+/// Each variant maps to a numeric level used in the hierarchical RBAC engine
+/// (the levels of the default [`ActionRegistry`]):
 ///
 /// | Variant | Level |
 /// |---------|-------|
@@ -71,7 +112,9 @@ use std::str::FromStr;
 /// | [`Superadmin`](Action::Superadmin) | 100 |
 ///
 /// Convert from a string via [`FromStr`] or use [`Action::level`] to get the numeric value.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Verbs of a custom [`ActionRegistry`] are passed to its methods by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Action {
     None,
     Read,
@@ -114,6 +157,12 @@ impl Action {
             Action::Superadmin => "superadmin",
         }
     }
+
+    /// Whether granting this action also grants `other`, according to the
+    /// default [`ActionRegistry`].
+    pub fn implies(self, other: Action) -> bool {
+        default_registry().implies(self.as_str(), other.as_str())
+    }
 }
 
 impl FromStr for Action {
@@ -146,24 +195,163 @@ impl AsRef<str> for Action {
     }
 }
 
-fn privilege_level(action: &str) -> i32 {
-    match action {
-        "none" => 0,
-        "read" => 10,
-        "create" => 20,
-        "update" => 30,
-        "delete" => 40,
-        "manage" => 50,
-        "admin" => 60,
-        "owner" | "*" => 90,
-        "superadmin" => 100,
-        _ => 10,
+/// Which actions exist and which ones imply which.
+///
+/// Actions with a [level](Self::levels) form a linear hierarchy: an action
+/// implies every action whose level is lower or equal. [Implications](Self::implies)
+/// add explicit edges for verbs that are not ordered, e.g. `approve` implied by
+/// `manage` but not by `delete`. Implication is transitive, and a level also
+/// implies what the lower levels imply.
+///
+/// The default registry holds the built-in levels (see [`Action`]) and treats
+/// unknown verbs as `read`; the functions of this module use it. Checks
+/// against a custom registry go through its methods, which take actions by
+/// name, or through a [`ScopeSet`] compiled with [`ScopeSet::compile_with`].
+///
+/// # Example
+///
+/// ```
+/// use usso::authorization::ActionRegistry;
+///
+/// let mut registry = ActionRegistry::default();
+/// registry.implies.insert("manage".into(), vec!["approve".into()]);
+/// registry.implies.insert("export".into(), vec!["read".into()]);
+/// registry.unknown_action = None;
+///
+/// assert!(registry.implies("admin", "approve"));
+/// assert!(!registry.implies("delete", "approve"));
+/// assert!(registry.implies("export", "read"));
+/// assert!(!registry.implies("export", "create"));
+/// assert!(!registry.implies("superadmin", "publish"));
+///
+/// let scopes = vec!["admin:orders".into(), "delete:billing".into()];
+/// assert!(registry.check_access(&scopes, "orders", Some("approve"), None, false));
+/// assert!(!registry.check_access(&scopes, "billing", Some("approve"), None, false));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionRegistry {
+    /// Linearly ordered actions and their levels.
+    pub levels: HashMap<String, i32>,
+    /// Actions each action directly implies.
+    pub implies: HashMap<String, Vec<String>>,
+    /// The action that verbs missing from both tables count as when scopes
+    /// are parsed leniently; with `None` they grant and are granted nothing.
+    /// Strict parsing ([`Scope`]) rejects them either way.
+    pub unknown_action: Option<String>,
+}
+
+impl Default for ActionRegistry {
+    fn default() -> Self {
+        let levels = [
+            ("none", 0),
+            ("read", 10),
+            ("create", 20),
+            ("update", 30),
+            ("delete", 40),
+            ("manage", 50),
+            ("admin", 60),
+            ("owner", 90),
+            ("*", 90),
+            ("superadmin", 100),
+        ];
+        ActionRegistry {
+            levels: levels
+                .into_iter()
+                .map(|(action, level)| (action.to_string(), level))
+                .collect(),
+            implies: HashMap::new(),
+            unknown_action: Some("read".to_string()),
+        }
+    }
+}
+
+impl ActionRegistry {
+    /// The level of `action` in this registry, if it has one.
+    pub fn level(&self, action: &str) -> Option<i32> {
+        self.levels.get(action).copied()
+    }
+
+    /// The registry's own name for `action`, if it defines it.
+    fn known(&self, action: &str) -> Option<&str> {
+        if let Some((name, _)) = self.levels.get_key_value(action) {
+            return Some(name);
+        }
+        if let Some((name, _)) = self.implies.get_key_value(action) {
+            return Some(name);
+        }
+        self.implies
+            .values()
+            .flatten()
+            .find(|implied| *implied == action)
+            .map(String::as_str)
+    }
+
+    /// Whether `action` has a level or appears in an implication.
+    pub fn is_known(&self, action: &str) -> bool {
+        self.known(action).is_some()
+    }
+
+    /// `action` itself if known, otherwise [`unknown_action`](Self::unknown_action).
+    fn resolve<'a>(&'a self, action: &'a str) -> Option<&'a str> {
+        self.known(action).or(self.unknown_action.as_deref())
+    }
+
+    /// Whether granting `granted` also grants `requested`.
+    pub fn implies(&self, granted: &str, requested: &str) -> bool {
+        let (Some(granted), Some(requested)) = (self.resolve(granted), self.resolve(requested))
+        else {
+            return false;
+        };
+        let requested_level = self.levels.get(requested);
+        let at_least = |action: &str| {
+            matches!(
+                (self.levels.get(action), requested_level),
+                (Some(level), Some(requested_level)) if level >= requested_level
+            )
+        };
+        if granted == requested || at_least(granted) {
+            return true;
+        }
+        if self.implies.is_empty() {
+            return false;
+        }
+
+        let mut seen = HashSet::from([granted]);
+        let mut pending = vec![granted];
+        while let Some(action) = pending.pop() {
+            if action == requested || at_least(action) {
+                return true;
+            }
+            let level = self.levels.get(action);
+            let direct = self.implies.get(action).into_iter().flatten();
+            // A level also implies what the lower levels imply.
+            let lower = self.implies.keys().filter(|other| {
+                matches!(
+                    (level, self.levels.get(other.as_str())),
+                    (Some(level), Some(other_level)) if other_level <= level
+                )
+            });
+            for next in direct.chain(lower) {
+                if seen.insert(next) {
+                    pending.push(next);
+                }
+            }
+        }
+        false
     }
 }
 
+/// The default [`ActionRegistry`], used by the functions of this module.
+fn default_registry() -> &'static Arc<ActionRegistry> {
+    static DEFAULT: OnceLock<Arc<ActionRegistry>> = OnceLock::new();
+    DEFAULT.get_or_init(Default::default)
+}
+
 /// Parse a scope string into its components.
 ///
-/// Returns `(action, path_segments, filters)`.
+/// Returns `(action, path_segments, filters)`. The deny marker of a deny scope
+/// is kept in the action (see [`is_deny_scope`]).
 ///
 /// # Examples
 ///
@@ -211,6 +399,568 @@ pub fn parse_scope(scope: &str) -> (String, Vec<String>, HashMap<String, String>
     (action, path_parts, filters)
 }
 
+/// Check whether a scope string is a deny scope (`!` or `deny:` prefix).
+///
+/// ```
+/// use usso::authorization::is_deny_scope;
+///
+/// assert!(is_deny_scope("!delete:billing"));
+/// assert!(is_deny_scope("deny:delete:billing"));
+/// assert!(!is_deny_scope("delete:billing"));
+/// ```
+pub fn is_deny_scope(scope: &str) -> bool {
+    split_deny(scope).0
+}
+
+/// Split the deny marker off a scope string.
+fn split_deny(scope: &str) -> (bool, &str) {
+    match scope.strip_prefix('!').or_else(|| scope.strip_prefix("deny:")) {
+        Some(rule) => (true, rule),
+        None => (false, scope),
+    }
+}
+
+/// Parse a scope string into (deny, action, path, filters).
+fn parse_rule(scope: &str) -> (bool, String, Vec<String>, HashMap<String, String>) {
+    let (deny, rule) = split_deny(scope);
+    let (action, path, filters) = parse_scope(rule);
+    (deny, action, path, filters)
+}
+
+/// A validated scope: `<action>:<segment>/<segment>...?<key>=<value>&...`.
+///
+/// Unlike [`parse_scope`], parsing is strict: the action must be a built-in
+/// [`Action`] (or, with [`parse_with`](Self::parse_with), a verb of the given
+/// [`ActionRegistry`]), path segments must not be empty, and every filter must be a `key=value` pair with a
+/// non-empty key and value and no duplicate keys.
+/// A leading `!` or `deny:` makes it a deny scope. Scopes display in canonical
+/// form (`!` for denies, action name, filters sorted by key) and
+/// (de)serialize as that string. The checks of a `Scope` follow the default
+/// registry; check custom verbs with [`ActionRegistry::check_access_scopes`]
+/// or a [`ScopeSet`] compiled with [`ScopeSet::compile_with`].
+///
+/// # Example
+///
+/// ```
+/// use usso::authorization::{Action, Scope};
+///
+/// let scope: Scope = "admin:billing/invoices?tenant_id=t1".parse().unwrap();
+/// assert_eq!(scope.action(), "admin");
+/// assert_eq!(scope.path(), ["billing", "invoices"]);
+/// assert!(scope.is_authorized("billing/invoices", Some(Action::Delete), None, false));
+/// assert_eq!(scope.to_string(), "admin:billing/invoices?tenant_id=t1");
+///
+/// assert!("approve:billing".parse::<Scope>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Scope {
+    deny: bool,
+    action: String,
+    path: Vec<String>,
+    filters: HashMap<String, String>,
+}
+
+impl Scope {
+    /// Whether this is a deny scope.
+    pub fn is_deny(&self) -> bool {
+        self.deny
+    }
+
+    /// The name of the granted (or, for a deny scope, denied) action;
+    /// `owner` for `*`.
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// The resource path segments.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The filters restricting the scope.
+    pub fn filters(&self) -> &HashMap<String, String> {
+        &self.filters
+    }
+
+    /// Parse `scope` strictly, accepting the verbs of `registry` as well as
+    /// the built-in actions.
+    pub fn parse_with(scope: &str, registry: &ActionRegistry) -> Result<Self, ScopeParseError> {
+        let (deny, rule) = split_deny(scope);
+        let (rest, query) = match rule.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rule, None),
+        };
+        let (action, resource_path) = rest
+            .split_once(':')
+            .filter(|(action, _)| !action.is_empty())
+            .ok_or_else(|| ScopeParseError::MissingAction(scope.to_string()))?;
+        let action = match action.parse::<Action>() {
+            Ok(action) => action.as_str().to_string(),
+            Err(()) if registry.is_known(action) => action.to_string(),
+            Err(()) => return Err(ScopeParseError::UnknownAction(action.to_string())),
+        };
+
+        let path: Vec<String> = resource_path.split('/').map(str::to_string).collect();
+        if path.iter().any(String::is_empty) {
+            return Err(ScopeParseError::EmptySegment(scope.to_string()));
+        }
+
+        let mut filters = HashMap::new();
+        for pair in query.into_iter().flat_map(|query| query.split('&')) {
+            let (key, value) = pair
+                .split_once('=')
+                .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+                .ok_or_else(|| ScopeParseError::InvalidFilter(pair.to_string()))?;
+            if filters.insert(key.to_string(), value.to_string()).is_some() {
+                return Err(ScopeParseError::InvalidFilter(format!("duplicate key {key}")));
+            }
+        }
+
+        Ok(Scope {
+            deny,
+            action,
+            path,
+            filters,
+        })
+    }
+
+    /// Check whether this scope grants access to a resource, like
+    /// [`is_authorized`] without re-parsing.
+    pub fn is_authorized(
+        &self,
+        requested_path: &str,
+        requested_action: Option<Action>,
+        requested_filter: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        self.grants(
+            default_registry(),
+            requested_path,
+            requested_action.map(Action::as_str),
+            requested_filter,
+            strict,
+        )
+    }
+
+    /// Check whether this scope is implied by `super_scope`, like
+    /// [`is_subset_scope`] without re-parsing.
+    pub fn is_subset_of(&self, super_scope: &Scope) -> bool {
+        self.implied_by(default_registry(), super_scope)
+    }
+
+    /// [`is_authorized`](Self::is_authorized) with the actions of `registry`.
+    fn grants(
+        &self,
+        registry: &ActionRegistry,
+        requested_path: &str,
+        requested_action: Option<&str>,
+        requested_filter: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        !self.deny
+            && authorizes(
+                registry,
+                (&self.action, &self.path, &self.filters),
+                requested_path,
+                requested_action,
+                requested_filter,
+                strict,
+            )
+    }
+
+    /// [`is_subset_of`](Self::is_subset_of) with the actions of `registry`.
+    fn implied_by(&self, registry: &ActionRegistry, super_scope: &Scope) -> bool {
+        !self.deny
+            && !super_scope.deny
+            && implies(
+                registry,
+                (&super_scope.action, &super_scope.path, &super_scope.filters),
+                (&self.action, &self.path, &self.filters),
+            )
+    }
+
+    /// Whether this deny scope denies the request.
+    fn denies(
+        &self,
+        registry: &ActionRegistry,
+        requested_path: &[String],
+        requested_action: Option<&str>,
+        requested_filter: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        self.deny
+            && denies(
+                registry,
+                (&self.action, &self.path, &self.filters),
+                requested_path,
+                requested_action,
+                requested_filter,
+                strict,
+            )
+    }
+
+    /// Whether this deny scope takes away part of what `grant` allows.
+    fn restricts(&self, registry: &ActionRegistry, grant: &Scope) -> bool {
+        self.deny
+            && overlaps(
+                registry,
+                (&self.action, &self.path, &self.filters),
+                (&grant.action, &grant.path, &grant.filters),
+            )
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ScopeParseError;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(scope, default_registry())
+    }
+}
+
+impl TryFrom<String> for Scope {
+    type Error = ScopeParseError;
+
+    fn try_from(scope: String) -> Result<Self, Self::Error> {
+        scope.parse()
+    }
+}
+
+impl From<Scope> for String {
+    fn from(scope: Scope) -> Self {
+        scope.to_string()
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.deny {
+            f.write_str("!")?;
+        }
+        write!(f, "{}:{}", self.action, self.path.join("/"))?;
+        let mut filters: Vec<_> = self.filters.iter().collect();
+        filters.sort();
+        for (i, (key, value)) in filters.into_iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(f, "{separator}{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A set of [`Scope`]s compiled once (e.g. from [`UserData::scopes`]) for
+/// repeated authorization checks.
+///
+/// Scopes are indexed in a trie over their path segments, last segment
+/// first (paths match from the end, see [`is_path_match`]), so a query only
+/// looks at scopes whose path can match the requested resource instead of
+/// re-parsing and scanning every scope string. Deny scopes are kept apart and
+/// checked first. Answers agree with [`check_access`], [`get_scope_filters`]
+/// and [`has_subset_scope`], or with the methods of the [`ActionRegistry`]
+/// the set was compiled with ([`compile_with`](Self::compile_with)). Like
+/// those methods, the set's checks take actions by name.
+///
+/// # Example
+///
+/// ```
+/// use usso::authorization::{Action, ScopeSet};
+///
+/// let scopes = vec!["read:users".into(), "admin:billing/*?tenant_id=t1".into()];
+/// let set = ScopeSet::compile(&scopes).unwrap();
+/// assert!(set.check_access("users", Some("read"), None, false));
+/// assert!(!set.check_access("users", Some(Action::Update.as_str()), None, false));
+/// assert_eq!(set.get_scope_filters("delete", "billing/invoices").len(), 1);
+/// assert!(set.has_subset_scope(&"read:billing/invoices?tenant_id=t1".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScopeSet {
+    scopes: Vec<Scope>,
+    /// Index of the grant scopes.
+    index: PathTrie,
+    /// Positions of the deny scopes.
+    denies: Vec<usize>,
+    /// The actions the scopes are checked with.
+    registry: Arc<ActionRegistry>,
+}
+
+/// A trie node over reversed path segments.
+#[derive(Debug, Clone, Default)]
+struct PathTrie {
+    /// Scopes whose path ends at this node, by position in the set.
+    scopes: Vec<usize>,
+    /// Children for literal segments.
+    literals: HashMap<String, PathTrie>,
+    /// Children for segments containing `*`.
+    patterns: Vec<(String, PathTrie)>,
+}
+
+impl PathTrie {
+    fn insert(&mut self, reversed_path: &[String], scope: usize) {
+        let Some((segment, rest)) = reversed_path.split_first() else {
+            self.scopes.push(scope);
+            return;
+        };
+        let child = if segment.contains('*') {
+            match self.patterns.iter().position(|(pattern, _)| pattern == segment) {
+                Some(i) => &mut self.patterns[i].1,
+                None => {
+                    self.patterns.push((segment.clone(), PathTrie::default()));
+                    &mut self.patterns.last_mut().unwrap().1
+                }
+            }
+        } else {
+            self.literals.entry(segment.clone()).or_default()
+        };
+        child.insert(rest, scope);
+    }
+
+    fn children(&self) -> impl Iterator<Item = &PathTrie> {
+        self.literals.values().chain(self.patterns.iter().map(|(_, child)| child))
+    }
+
+    fn collect_all(&self, out: &mut Vec<usize>) {
+        out.extend(&self.scopes);
+        for child in self.children() {
+            child.collect_all(out);
+        }
+    }
+
+    /// Collect the scopes whose segments match the overlapping part of the
+    /// request (`reversed_request`, from its last segment).
+    fn candidates(&self, reversed_request: &[String], first: bool, out: &mut Vec<usize>) {
+        out.extend(&self.scopes);
+        let Some((segment, rest)) = reversed_request.split_first() else {
+            // Longer scope paths are decided by the full path check.
+            for child in self.children() {
+                child.collect_all(out);
+            }
+            return;
+        };
+        if !first && segment == "*" {
+            for child in self.children() {
+                child.candidates(rest, false, out);
+            }
+            return;
+        }
+        if let Some(child) = self.literals.get(segment) {
+            child.candidates(rest, false, out);
+        }
+        for (pattern, child) in &self.patterns {
+            if wildcard_match(segment, pattern) {
+                child.candidates(rest, false, out);
+            }
+        }
+    }
+}
+
+impl ScopeSet {
+    /// Parse and compile `scopes`, failing on the first invalid one.
+    pub fn compile(scopes: &[String]) -> Result<Self, ScopeParseError> {
+        Self::compile_with(scopes, default_registry())
+    }
+
+    /// Like [`compile`](Self::compile), with the actions of `registry`: its
+    /// verbs parse, and the set's checks follow it.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use usso::authorization::{ActionRegistry, ScopeSet};
+    ///
+    /// let mut registry = ActionRegistry::default();
+    /// registry.implies.insert("manage".into(), vec!["approve".into()]);
+    /// let registry = Arc::new(registry);
+    ///
+    /// let set = ScopeSet::compile_with(&["manage:orders".into()], &registry).unwrap();
+    /// assert!(set.check_access("orders", Some("approve"), None, false));
+    /// assert!(ScopeSet::compile(&["approve:orders".into()]).is_err());
+    /// ```
+    pub fn compile_with(
+        scopes: &[String],
+        registry: &Arc<ActionRegistry>,
+    ) -> Result<Self, ScopeParseError> {
+        let scopes = scopes
+            .iter()
+            .map(|scope| Scope::parse_with(scope, registry))
+            .collect::<Result<Vec<Scope>, _>>()?;
+        Ok(Self::index(scopes, registry.clone()))
+    }
+
+    /// Parse and compile `scopes`, skipping the invalid ones.
+    ///
+    /// Tokens often carry scopes that are not resource scopes (`openid`,
+    /// `profile`, legacy verbs); rather than failing the whole set, they are
+    /// left out and their parse errors returned next to it.
+    pub fn compile_lossy(scopes: &[String]) -> (Self, Vec<ScopeParseError>) {
+        Self::compile_lossy_with(scopes, default_registry())
+    }
+
+    /// Like [`compile_lossy`](Self::compile_lossy), with the actions of
+    /// `registry` (see [`compile_with`](Self::compile_with)).
+    pub fn compile_lossy_with(
+        scopes: &[String],
+        registry: &Arc<ActionRegistry>,
+    ) -> (Self, Vec<ScopeParseError>) {
+        let mut errors = Vec::new();
+        let scopes = scopes
+            .iter()
+            .filter_map(|scope| {
+                Scope::parse_with(scope, registry)
+                    .map_err(|err| errors.push(err))
+                    .ok()
+            })
+            .collect();
+        (Self::index(scopes, registry.clone()), errors)
+    }
+
+    /// Compile the `scopes` claim of a verified token, skipping the scopes
+    /// that do not parse (see [`compile_lossy`](Self::compile_lossy)).
+    pub fn from_user_data<E>(user: &UserData<E>) -> Self {
+        Self::compile_lossy(user.scopes.as_deref().unwrap_or_default()).0
+    }
+
+    /// The registry the set's checks follow.
+    pub fn registry(&self) -> &ActionRegistry {
+        &self.registry
+    }
+
+    /// The compiled scopes, in their original order.
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    /// Number of scopes in the set.
+    pub fn len(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Returns `true` if the set holds no scope.
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+
+    fn deny_scopes(&self) -> impl Iterator<Item = &Scope> {
+        self.denies.iter().map(|&i| &self.scopes[i])
+    }
+
+    /// Positions of the grant scopes whose path may match `path`, in order.
+    fn candidates(&self, path: &[String]) -> Vec<usize> {
+        let reversed: Vec<String> = path.iter().rev().cloned().collect();
+        let mut out = Vec::new();
+        self.index.candidates(&reversed, true, &mut out);
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Like [`check_access`].
+    pub fn check_access(
+        &self,
+        resource_path: &str,
+        action: Option<&str>,
+        filters: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        let path: Vec<String> = resource_path.split('/').map(str::to_string).collect();
+        if self
+            .deny_scopes()
+            .any(|deny| deny.denies(&self.registry, &path, action, filters, strict))
+        {
+            return false;
+        }
+        self.candidates(&path).into_iter().any(|i| {
+            self.scopes[i].grants(&self.registry, resource_path, action, filters, strict)
+        })
+    }
+
+    /// Like [`get_scope_filters`].
+    pub fn get_scope_filters(&self, action: &str, resource: &str) -> Vec<HashMap<String, String>> {
+        let path: Vec<String> = resource.split('/').map(str::to_string).collect();
+        let denies: Vec<&HashMap<String, String>> = self
+            .deny_scopes()
+            .filter(|deny| deny.denies(&self.registry, &path, Some(action), None, false))
+            .map(|deny| &deny.filters)
+            .collect();
+        self.candidates(&path)
+            .into_iter()
+            .map(|i| &self.scopes[i])
+            .filter(|scope| self.registry.implies(&scope.action, action))
+            .filter(|scope| is_path_match(&scope.path, &path, false))
+            .filter(|scope| !denies.iter().any(|deny| filters_overlap(deny, &scope.filters)))
+            .map(|scope| scope.filters.clone())
+            .collect()
+    }
+
+    /// Like [`explain_access`], over every scope of the set (in canonical form).
+    pub fn explain_access(
+        &self,
+        resource_path: &str,
+        action: Option<&str>,
+        filters: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> AccessDecision {
+        AccessDecision::evaluate(
+            &self.registry,
+            self.scopes.iter().map(|scope| {
+                (
+                    scope.to_string(),
+                    scope.deny,
+                    scope.action.as_str(),
+                    scope.path.as_slice(),
+                    &scope.filters,
+                )
+            }),
+            resource_path,
+            action,
+            filters,
+            strict,
+        )
+    }
+
+    /// Like [`has_subset_scope`]: whether the set can delegate `subset_scope`.
+    pub fn has_subset_scope(&self, subset_scope: &Scope) -> bool {
+        if subset_scope.deny {
+            return true;
+        }
+        if self
+            .deny_scopes()
+            .any(|deny| deny.restricts(&self.registry, subset_scope))
+        {
+            return false;
+        }
+        self.candidates(&subset_scope.path)
+            .into_iter()
+            .any(|i| subset_scope.implied_by(&self.registry, &self.scopes[i]))
+    }
+
+    /// Index `scopes`, checked with `registry`.
+    fn index(scopes: Vec<Scope>, registry: Arc<ActionRegistry>) -> Self {
+        let mut index = PathTrie::default();
+        let mut denies = Vec::new();
+        for (i, scope) in scopes.iter().enumerate() {
+            if scope.deny {
+                denies.push(i);
+                continue;
+            }
+            let reversed: Vec<String> = scope.path.iter().rev().cloned().collect();
+            index.insert(&reversed, i);
+        }
+        ScopeSet {
+            scopes,
+            index,
+            denies,
+            registry,
+        }
+    }
+}
+
+impl FromIterator<Scope> for ScopeSet {
+    /// Index `scopes`, checked with the default registry.
+    fn from_iter<I: IntoIterator<Item = Scope>>(scopes: I) -> Self {
+        Self::index(scopes.into_iter().collect(), default_registry().clone())
+    }
+}
+
 fn normalize_path(path: &[String]) -> Vec<String> {
     path.to_vec()
 }
@@ -310,7 +1060,9 @@ pub fn is_filter_match(user_filters: &HashMap<String, String>, requested_filters
 /// This is the core authorization check. It verifies:
 /// - Path match (with wildcard support)
 /// - Filter match (with wildcard support)
-/// - Action privilege level (hierarchical)
+/// - Action (the scope's action implies the requested one, see [`ActionRegistry`])
+///
+/// A deny scope never authorizes anything.
 pub fn is_authorized(
     user_scope: &str,
     requested_path: &str,
@@ -318,30 +1070,111 @@ pub fn is_authorized(
     requested_filter: Option<&HashMap<String, String>>,
     strict: bool,
 ) -> bool {
-    let (user_action, user_path, user_filters) = parse_scope(user_scope);
+    default_registry().is_authorized(
+        user_scope,
+        requested_path,
+        requested_action.map(Action::as_str),
+        requested_filter,
+        strict,
+    )
+}
 
-    if !is_path_match(&user_path, &requested_path.split('/').map(|s| s.to_string()).collect::<Vec<_>>(), strict) {
+/// The authorization check shared by [`is_authorized`] and [`Scope::is_authorized`].
+fn authorizes(
+    registry: &ActionRegistry,
+    (user_action, user_path, user_filters): (&str, &[String], &HashMap<String, String>),
+    requested_path: &str,
+    requested_action: Option<&str>,
+    requested_filter: Option<&HashMap<String, String>>,
+    strict: bool,
+) -> bool {
+    if !is_path_match(user_path, &requested_path.split('/').map(|s| s.to_string()).collect::<Vec<_>>(), strict) {
         return false;
     }
 
     if let Some(filters) = requested_filter {
-        if !is_filter_match(&user_filters, filters) {
+        if !is_filter_match(user_filters, filters) {
             return false;
         }
     }
 
     if let Some(action) = requested_action {
-        let user_level = privilege_level(&user_action);
-        let req_level = action.level();
-        return user_level >= req_level;
+        return registry.implies(user_action, action);
     }
 
     true
 }
 
+/// Whether a deny scope's (action, path, filters) denies the request: the
+/// requested action implies the denied one.
+///
+/// A request without an action is denied when reading is; a request without
+/// filters is denied whatever the deny's filters.
+fn denies(
+    registry: &ActionRegistry,
+    (deny_action, deny_path, deny_filters): (&str, &[String], &HashMap<String, String>),
+    requested_path: &[String],
+    requested_action: Option<&str>,
+    requested_filter: Option<&HashMap<String, String>>,
+    strict: bool,
+) -> bool {
+    registry.implies(requested_action.unwrap_or("read"), deny_action)
+        && is_path_match(deny_path, requested_path, strict)
+        && requested_filter.is_none_or(|filters| is_filter_match(deny_filters, filters))
+}
+
+/// Whether a deny scope's (action, path, filters) takes away part of what a
+/// grant's allows.
+fn overlaps(
+    registry: &ActionRegistry,
+    (deny_action, deny_path, deny_filters): (&str, &[String], &HashMap<String, String>),
+    (grant_action, grant_path, grant_filters): (&str, &[String], &HashMap<String, String>),
+) -> bool {
+    registry.implies(grant_action, deny_action)
+        && (is_path_match(deny_path, grant_path, false)
+            || is_path_match(grant_path, deny_path, false))
+        && filters_overlap(deny_filters, grant_filters)
+}
+
+/// Whether some request can satisfy both filter sets: every key they share
+/// has values that can match each other.
+fn filters_overlap(a: &HashMap<String, String>, b: &HashMap<String, String>) -> bool {
+    a.iter().all(|(key, value)| {
+        b.get(key)
+            .is_none_or(|other| wildcard_match(other, value) || wildcard_match(value, other))
+    })
+}
+
+/// Whether any deny scope string denies the request.
+fn is_denied(
+    registry: &ActionRegistry,
+    user_scopes: &[String],
+    requested_path: &str,
+    requested_action: Option<&str>,
+    requested_filter: Option<&HashMap<String, String>>,
+    strict: bool,
+) -> bool {
+    let requested_parts: Vec<String> = requested_path.split('/').map(str::to_string).collect();
+    user_scopes
+        .iter()
+        .filter(|scope| is_deny_scope(scope))
+        .any(|scope| {
+            let (_, action, path, filters) = parse_rule(scope);
+            denies(
+                registry,
+                (&action, &path, &filters),
+                &requested_parts,
+                requested_action,
+                requested_filter,
+                strict,
+            )
+        })
+}
+
 /// Check whether ANY of the user's scopes grant access to a resource.
 ///
-/// Returns `true` if at least one scope satisfies the request.
+/// Returns `true` if at least one scope satisfies the request and no deny
+/// scope denies it.
 ///
 /// # Example
 ///
@@ -360,17 +1193,164 @@ pub fn check_access(
     filters: Option<&HashMap<String, String>>,
     strict: bool,
 ) -> bool {
-    for scope in user_scopes {
-        if is_authorized(scope, resource_path, action, filters, strict) {
-            return true;
+    let action = action.map(Action::as_str);
+    default_registry().check_access(user_scopes, resource_path, action, filters, strict)
+}
+
+/// Check whether any pre-parsed scope grants access to a resource, like
+/// [`check_access`] without re-parsing.
+///
+/// # Example
+///
+/// ```
+/// use usso::authorization::{check_access_scopes, Action, Scope};
+///
+/// let scopes: Vec<Scope> = ["read:users", "admin:reports"]
+///     .iter()
+///     .map(|scope| scope.parse().unwrap())
+///     .collect();
+/// assert!(check_access_scopes(&scopes, "reports", Some(Action::Delete), None, false));
+/// assert!(!check_access_scopes(&scopes, "users", Some(Action::Update), None, false));
+/// ```
+pub fn check_access_scopes(
+    user_scopes: &[Scope],
+    resource_path: &str,
+    action: Option<Action>,
+    filters: Option<&HashMap<String, String>>,
+    strict: bool,
+) -> bool {
+    let action = action.map(Action::as_str);
+    default_registry().check_access_scopes(user_scopes, resource_path, action, filters, strict)
+}
+
+/// Why a scope did not grant the requested access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessMismatch {
+    /// The scope's path does not match the resource.
+    Path,
+    /// The scope's filters are not satisfied by the requested filters.
+    Filters,
+    /// The scope's action does not imply the requested action.
+    Level,
+}
+
+/// A grant scope that did not grant access, with every check it failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedScope {
+    pub scope: String,
+    pub mismatches: Vec<AccessMismatch>,
+}
+
+/// The outcome of [`explain_access`], for audit logs and debug responses.
+///
+/// `allowed` is always what [`check_access`] returns for the same request: a
+/// scope granted access and no deny scope denied it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessDecision {
+    pub allowed: bool,
+    pub resource: String,
+    /// The requested action, by name.
+    pub action: Option<String>,
+    pub filters: Option<HashMap<String, String>>,
+    /// Every scope that was evaluated, in order.
+    pub considered: Vec<String>,
+    /// The first scope that grants access.
+    pub granted_by: Option<String>,
+    /// The first deny scope that denies access, overriding any grant.
+    pub denied_by: Option<String>,
+    /// The grant scopes that do not grant access.
+    pub rejected: Vec<RejectedScope>,
+}
+
+impl AccessDecision {
+    fn evaluate<'a>(
+        registry: &ActionRegistry,
+        scopes: impl IntoIterator<
+            Item = (String, bool, &'a str, &'a [String], &'a HashMap<String, String>),
+        >,
+        resource_path: &str,
+        action: Option<&str>,
+        filters: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> Self {
+        let requested_path: Vec<String> = resource_path.split('/').map(str::to_string).collect();
+        let mut decision = AccessDecision {
+            allowed: false,
+            resource: resource_path.to_string(),
+            action: action.map(str::to_string),
+            filters: filters.cloned(),
+            considered: Vec::new(),
+            granted_by: None,
+            denied_by: None,
+            rejected: Vec::new(),
+        };
+        for (scope, deny, scope_action, path, scope_filters) in scopes {
+            decision.considered.push(scope.clone());
+            if deny {
+                let rule = (scope_action, path, scope_filters);
+                if decision.denied_by.is_none()
+                    && denies(registry, rule, &requested_path, action, filters, strict)
+                {
+                    decision.denied_by = Some(scope);
+                }
+                continue;
+            }
+            let mut mismatches = Vec::new();
+            if !is_path_match(path, &requested_path, strict) {
+                mismatches.push(AccessMismatch::Path);
+            }
+            if filters.is_some_and(|filters| !is_filter_match(scope_filters, filters)) {
+                mismatches.push(AccessMismatch::Filters);
+            }
+            if action.is_some_and(|action| !registry.implies(scope_action, action)) {
+                mismatches.push(AccessMismatch::Level);
+            }
+            if !mismatches.is_empty() {
+                decision.rejected.push(RejectedScope { scope, mismatches });
+            } else if decision.granted_by.is_none() {
+                decision.granted_by = Some(scope);
+            }
         }
+        decision.allowed = decision.granted_by.is_some() && decision.denied_by.is_none();
+        decision
     }
-    false
+}
+
+/// Like [`check_access`], but explain the result: which scopes were
+/// considered, which one granted access, which deny scope overrode it, and
+/// why the other grants did not apply.
+///
+/// # Example
+///
+/// ```
+/// use usso::authorization::{explain_access, AccessMismatch, Action};
+///
+/// let scopes = vec!["read:users".into(), "admin:billing/*".into()];
+/// let decision = explain_access(&scopes, "users", Some(Action::Delete), None, false);
+/// assert!(!decision.allowed);
+/// assert_eq!(decision.rejected[0].mismatches, [AccessMismatch::Level]);
+/// assert_eq!(decision.rejected[1].mismatches, [AccessMismatch::Path]);
+///
+/// let json = serde_json::to_value(&decision).unwrap();
+/// assert_eq!(json["rejected"][0]["mismatches"][0], "level");
+/// ```
+pub fn explain_access(
+    user_scopes: &[String],
+    resource_path: &str,
+    action: Option<Action>,
+    filters: Option<&HashMap<String, String>>,
+    strict: bool,
+) -> AccessDecision {
+    let action = action.map(Action::as_str);
+    default_registry().explain_access(user_scopes, resource_path, action, filters, strict)
 }
 
 /// Check whether any user scope contains (is a superset of) the given scope.
 ///
-/// Useful for checking if a user has permission to delegate a scope.
+/// Useful for checking if a user has permission to delegate a scope. A scope
+/// that overlaps one of the user's deny scopes cannot be delegated, since it
+/// would grant what the deny takes away; a deny scope can always be.
 ///
 /// # Example
 ///
@@ -379,46 +1359,44 @@ pub fn check_access(
 ///
 /// let scopes = vec!["admin:*".into()];
 /// assert!(has_subset_scope("read:users", &scopes));
+///
+/// let scopes = vec!["admin:billing/*".into(), "!delete:billing/invoices".into()];
+/// assert!(has_subset_scope("update:billing/invoices", &scopes));
+/// assert!(!has_subset_scope("admin:billing/invoices", &scopes));
+/// assert!(!has_subset_scope("admin:billing/*", &scopes));
 /// ```
 pub fn has_subset_scope(subset_scope: &str, user_scopes: &[String]) -> bool {
-    for user_scope in user_scopes {
-        if is_subset_scope(subset_scope, user_scope) {
-            return true;
-        }
-    }
-    false
+    default_registry().has_subset_scope(subset_scope, user_scopes)
 }
 
 /// Return filters extracted from user scopes that match the given action and resource.
 ///
-/// Filters are extracted from scopes whose privilege level >= requested action
-/// and whose resource path matches the requested resource.
+/// Filters are extracted from scopes whose action implies the requested action
+/// and whose resource path matches the requested resource. A deny scope
+/// covering the action and resource drops every grant whose filters overlap
+/// its own (filters cannot express exclusions), so a deny without filters
+/// leaves none.
 ///
 /// # Example
 ///
 /// ```
+/// use std::collections::HashMap;
 /// use usso::authorization::{get_scope_filters, Action};
 ///
 /// let scopes = vec!["read:users?tenant_id=t1".into(), "admin:*".into()];
 /// let filters = get_scope_filters(Action::Read, "users", &scopes);
 /// assert_eq!(filters.len(), 2);
+///
+/// let scopes = vec![
+///     "read:users?tenant_id=t1".into(),
+///     "read:users?tenant_id=t2".into(),
+///     "!read:users?tenant_id=t2".into(),
+/// ];
+/// let filters = get_scope_filters(Action::Read, "users", &scopes);
+/// assert_eq!(filters, [HashMap::from([("tenant_id".into(), "t1".into())])]);
 /// ```
 pub fn get_scope_filters(action: Action, resource: &str, user_scopes: &[String]) -> Vec<HashMap<String, String>> {
-    let action_level = action.level();
-    let requested_parts: Vec<String> = resource.split('/').map(|s| s.to_string()).collect();
-    let mut matched = Vec::new();
-    for scope in user_scopes {
-        let (scope_action, scope_path, scope_filters) = parse_scope(scope);
-        let scope_level = privilege_level(&scope_action);
-        if scope_level < action_level {
-            continue;
-        }
-        if !is_path_match(&scope_path, &requested_parts, false) {
-            continue;
-        }
-        matched.push(scope_filters);
-    }
-    matched
+    default_registry().get_scope_filters(action.as_str(), resource, user_scopes)
 }
 
 /// Return the broadest (most restrictive) scope filter from a list.
@@ -475,7 +1453,7 @@ pub fn broadest_scope_filter(filters: &[HashMap<String, String>]) -> HashMap<Str
 /// Check owner-level authorization for a resource.
 ///
 /// Grants access if the requested resource filter matches the user's ID
-/// (or owner_id / workspace_id) and the user's privilege level is sufficient.
+/// (or owner_id / workspace_id) and `self_action` implies the requested action.
 ///
 /// # Example
 ///
@@ -494,27 +1472,22 @@ pub fn owner_authorization(
     owner_id: Option<&str>,
     workspace_id: Option<&str>,
 ) -> bool {
-    let uid = owner_id.or(user_id).or(workspace_id);
-
-    if let (Some(uid), Some(filter)) = (uid, requested_filter) {
-        let matches = filter.get("owner_id").is_some_and(|v| v == uid)
-            || filter.get("user_id").is_some_and(|v| v == uid)
-            || filter.get("workspace_id").is_some_and(|v| v == uid);
-
-        if matches {
-            let user_level = self_action.unwrap_or(Action::Read).level();
-            let req_level = action.unwrap_or(Action::Read).level();
-            return user_level >= req_level;
-        }
-    }
-    false
+    default_registry().owner_authorization(
+        requested_filter,
+        user_id,
+        self_action.map(Action::as_str),
+        action.map(Action::as_str),
+        owner_id,
+        workspace_id,
+    )
 }
 
 /// Get common scopes between two scope lists.
 ///
 /// Removes scopes from `scopes_a` that are not permitted by `scopes_b`,
 /// and adds any permitted scopes from `scopes_b` that are subsets of
-/// the removed scopes.
+/// the removed scopes. Deny scopes from both lists are kept, so the result
+/// never allows what either list denies.
 ///
 /// # Example
 ///
@@ -525,60 +1498,46 @@ pub fn owner_authorization(
 /// let b = vec!["read:users".into()];
 /// let common = get_common_scopes(&a, &b);
 /// assert!(common.contains(&"read:users".to_string()));
+///
+/// let b = vec!["admin:users".into(), "!delete:users".into()];
+/// let common = get_common_scopes(&a, &b);
+/// assert!(common.contains(&"!delete:users".to_string()));
 /// ```
 pub fn get_common_scopes(scopes_a: &[String], scopes_b: &[String]) -> Vec<String> {
-    let not_permitted: Vec<String> = scopes_a
-        .iter()
-        .filter(|scope| !has_subset_scope(scope, scopes_b))
-        .cloned()
-        .collect();
-
-    if not_permitted.is_empty() {
-        return scopes_a.to_vec();
-    }
-
-    let new_permitted: Vec<String> = scopes_b
-        .iter()
-        .filter(|scope| has_subset_scope(scope, &not_permitted))
-        .cloned()
-        .collect();
-
-    let mut result: Vec<String> = scopes_a
-        .iter()
-        .filter(|s| !not_permitted.contains(s))
-        .cloned()
-        .collect();
-    result.extend(new_permitted);
-    result.sort();
-    result.dedup();
-    result
+    default_registry().get_common_scopes(scopes_a, scopes_b)
 }
 
 /// Check whether one scope is a subset of another (i.e. `subset_scope` is
 /// implied by `super_scope`).
 ///
-/// A scope A is a subset of scope B if B has equal or higher privilege, the
-/// paths match, and B's filters are a superset of A's filters.
+/// A scope A is a subset of scope B if B's action implies A's, the
+/// paths match, and B's filters are a superset of A's filters. Deny scopes
+/// are neither subsets nor supersets of any scope.
 pub fn is_subset_scope(subset_scope: &str, super_scope: &str) -> bool {
-    let (child_action, child_path, child_filters) = parse_scope(subset_scope);
-    let (parent_action, parent_path, parent_filters) = parse_scope(super_scope);
+    default_registry().is_subset_scope(subset_scope, super_scope)
+}
 
-    let child_level = privilege_level(&child_action);
-    let parent_level = privilege_level(&parent_action);
-    if parent_level < child_level {
+/// The subset check shared by [`is_subset_scope`] and [`Scope::is_subset_of`]:
+/// whether the `parent` (action, path, filters) implies the `child`.
+fn implies(
+    registry: &ActionRegistry,
+    (parent_action, parent_path, parent_filters): (&str, &[String], &HashMap<String, String>),
+    (child_action, child_path, child_filters): (&str, &[String], &HashMap<String, String>),
+) -> bool {
+    if !registry.implies(parent_action, child_action) {
         return false;
     }
 
     let child_path_str = child_path.join("/");
     if !is_path_match(
-        &parent_path,
+        parent_path,
         &child_path_str.split('/').map(|s| s.to_string()).collect::<Vec<_>>(),
         false,
     ) {
         return false;
     }
 
-    for (k, v) in &parent_filters {
+    for (k, v) in parent_filters {
         match child_filters.get(k) {
             Some(cv) if cv == v => {}
             _ => return false,
@@ -586,3 +1545,226 @@ pub fn is_subset_scope(subset_scope: &str, super_scope: &str) -> bool {
     }
     true
 }
+
+/// The functions of this module with the actions of a custom registry.
+///
+/// Each method answers like the function of the same name does with the
+/// default registry, with actions given by name so that the registry's own
+/// verbs can be requested.
+impl ActionRegistry {
+    /// Like [`is_authorized`].
+    pub fn is_authorized(
+        &self,
+        user_scope: &str,
+        requested_path: &str,
+        requested_action: Option<&str>,
+        requested_filter: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        let (deny, user_action, user_path, user_filters) = parse_rule(user_scope);
+        !deny
+            && authorizes(
+                self,
+                (&user_action, &user_path, &user_filters),
+                requested_path,
+                requested_action,
+                requested_filter,
+                strict,
+            )
+    }
+
+    /// Like [`check_access`].
+    pub fn check_access(
+        &self,
+        user_scopes: &[String],
+        resource_path: &str,
+        action: Option<&str>,
+        filters: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        if is_denied(self, user_scopes, resource_path, action, filters, strict) {
+            return false;
+        }
+        user_scopes
+            .iter()
+            .any(|scope| self.is_authorized(scope, resource_path, action, filters, strict))
+    }
+
+    /// Like [`check_access_scopes`].
+    pub fn check_access_scopes(
+        &self,
+        user_scopes: &[Scope],
+        resource_path: &str,
+        action: Option<&str>,
+        filters: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> bool {
+        let path: Vec<String> = resource_path.split('/').map(str::to_string).collect();
+        if user_scopes
+            .iter()
+            .any(|scope| scope.denies(self, &path, action, filters, strict))
+        {
+            return false;
+        }
+        user_scopes
+            .iter()
+            .any(|scope| scope.grants(self, resource_path, action, filters, strict))
+    }
+
+    /// Like [`explain_access`].
+    pub fn explain_access(
+        &self,
+        user_scopes: &[String],
+        resource_path: &str,
+        action: Option<&str>,
+        filters: Option<&HashMap<String, String>>,
+        strict: bool,
+    ) -> AccessDecision {
+        let parsed: Vec<_> = user_scopes
+            .iter()
+            .map(|scope| (scope, parse_rule(scope)))
+            .collect();
+        AccessDecision::evaluate(
+            self,
+            parsed.iter().map(|(scope, (deny, scope_action, path, scope_filters))| {
+                (
+                    scope.to_string(),
+                    *deny,
+                    scope_action.as_str(),
+                    path.as_slice(),
+                    scope_filters,
+                )
+            }),
+            resource_path,
+            action,
+            filters,
+            strict,
+        )
+    }
+
+    /// Like [`has_subset_scope`].
+    pub fn has_subset_scope(&self, subset_scope: &str, user_scopes: &[String]) -> bool {
+        let (deny, action, path, filters) = parse_rule(subset_scope);
+        if deny {
+            return true;
+        }
+        let restricted = user_scopes
+            .iter()
+            .filter(|scope| is_deny_scope(scope))
+            .any(|scope| {
+                let (_, deny_action, deny_path, deny_filters) = parse_rule(scope);
+                overlaps(
+                    self,
+                    (&deny_action, &deny_path, &deny_filters),
+                    (&action, &path, &filters),
+                )
+            });
+        !restricted
+            && user_scopes
+                .iter()
+                .any(|user_scope| self.is_subset_scope(subset_scope, user_scope))
+    }
+
+    /// Like [`is_subset_scope`].
+    pub fn is_subset_scope(&self, subset_scope: &str, super_scope: &str) -> bool {
+        let (child_deny, child_action, child_path, child_filters) = parse_rule(subset_scope);
+        let (parent_deny, parent_action, parent_path, parent_filters) = parse_rule(super_scope);
+        !child_deny
+            && !parent_deny
+            && implies(
+                self,
+                (&parent_action, &parent_path, &parent_filters),
+                (&child_action, &child_path, &child_filters),
+            )
+    }
+
+    /// Like [`get_scope_filters`].
+    pub fn get_scope_filters(
+        &self,
+        action: &str,
+        resource: &str,
+        user_scopes: &[String],
+    ) -> Vec<HashMap<String, String>> {
+        let requested_parts: Vec<String> = resource.split('/').map(|s| s.to_string()).collect();
+        let mut matched = Vec::new();
+        let mut denied = Vec::new();
+        for scope in user_scopes {
+            let (deny, scope_action, scope_path, scope_filters) = parse_rule(scope);
+            if deny {
+                let rule = (scope_action.as_str(), scope_path.as_slice(), &scope_filters);
+                if denies(self, rule, &requested_parts, Some(action), None, false) {
+                    denied.push(scope_filters);
+                }
+                continue;
+            }
+            if !self.implies(&scope_action, action) {
+                continue;
+            }
+            if !is_path_match(&scope_path, &requested_parts, false) {
+                continue;
+            }
+            matched.push(scope_filters);
+        }
+        matched.retain(|filters| !denied.iter().any(|deny| filters_overlap(deny, filters)));
+        matched
+    }
+
+    /// Like [`owner_authorization`].
+    pub fn owner_authorization(
+        &self,
+        requested_filter: Option<&HashMap<String, String>>,
+        user_id: Option<&str>,
+        self_action: Option<&str>,
+        action: Option<&str>,
+        owner_id: Option<&str>,
+        workspace_id: Option<&str>,
+    ) -> bool {
+        let uid = owner_id.or(user_id).or(workspace_id);
+
+        if let (Some(uid), Some(filter)) = (uid, requested_filter) {
+            let matches = filter.get("owner_id").is_some_and(|v| v == uid)
+                || filter.get("user_id").is_some_and(|v| v == uid)
+                || filter.get("workspace_id").is_some_and(|v| v == uid);
+
+            if matches {
+                return self.implies(self_action.unwrap_or("read"), action.unwrap_or("read"));
+            }
+        }
+        false
+    }
+
+    /// Like [`get_common_scopes`].
+    pub fn get_common_scopes(&self, scopes_a: &[String], scopes_b: &[String]) -> Vec<String> {
+        let denied_b = scopes_b
+            .iter()
+            .filter(|scope| is_deny_scope(scope) && !scopes_a.contains(scope))
+            .cloned();
+        let not_permitted: Vec<String> = scopes_a
+            .iter()
+            .filter(|scope| !self.has_subset_scope(scope, scopes_b))
+            .cloned()
+            .collect();
+
+        if not_permitted.is_empty() {
+            return scopes_a.iter().cloned().chain(denied_b).collect();
+        }
+
+        let new_permitted: Vec<String> = scopes_b
+            .iter()
+            .filter(|scope| !is_deny_scope(scope) && self.has_subset_scope(scope, &not_permitted))
+            .cloned()
+            .chain(denied_b)
+            .collect();
+
+        let mut result: Vec<String> = scopes_a
+            .iter()
+            .filter(|s| !not_permitted.contains(s))
+            .cloned()
+            .collect();
+        result.extend(new_permitted);
+        result.sort();
+        result.dedup();
+        result
+    }
+}
+
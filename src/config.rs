@@ -2,9 +2,23 @@
 //!
 //! Provides [`AuthConfig`] as the top-level configuration, along with
 //! [`HeaderConfig`] for JWT token extraction from HTTP headers/cookies and
-//! [`APIHeaderConfig`] for API key header extraction.
+//! [`APIHeaderConfig`] for API key header extraction, and
+//! [`TokenValidation`] for the algorithm allow-list and registered-claim
+//! checks applied to every token. [`HmacSecret`] holds the shared secret for
+//! HMAC-signed tokens, and [`ValidationMode`] selects local verification or
+//! introspection.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exceptions::{ClaimError, USSOError};
+use crate::introspection::IntrospectionClient;
+use crate::jwks::x509::X509Validation;
+use crate::schemas::Jwks;
 
 /// Configuration for extracting JWT tokens from HTTP headers and cookies.
 ///
@@ -94,10 +108,226 @@ impl APIHeaderConfig {
     }
 }
 
+/// Token checks: the algorithm allow-list enforced before signature
+/// verification and the registered-claim checks applied after it.
+///
+/// The same rules apply to every signing algorithm. Time-based checks allow
+/// [`leeway`](Self::leeway) seconds of clock skew; `iss` and `aud` are only
+/// checked when expected values are configured.
+///
+/// # Example
+///
+/// ```rust
+/// use usso::config::TokenValidation;
+///
+/// let validation = TokenValidation {
+///     algorithms: Some(vec!["EdDSA".into(), "ES512".into()]),
+///     issuers: Some(vec!["https://sso.usso.io".into()]),
+///     audiences: Some(vec!["billing-service".into()]),
+///     required_claims: vec!["exp".into(), "sub".into()],
+///     leeway: 30,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenValidation {
+    /// Accepted `alg` header values; `None` accepts every supported
+    /// algorithm, HS256/HS384/HS512 only with [`AuthConfig::hmac_secret`].
+    pub algorithms: Option<Vec<String>>,
+    /// Accepted `iss` values; `None` disables the check.
+    pub issuers: Option<Vec<String>>,
+    /// Accepted `aud` values (any overlap passes); `None` disables the check.
+    pub audiences: Option<Vec<String>>,
+    /// Claims that must be present in the token.
+    pub required_claims: Vec<String>,
+    /// Allowed clock skew in seconds for `exp`, `nbf` and `iat`.
+    pub leeway: u64,
+    /// Reject tokens whose `exp` is in the past.
+    pub validate_exp: bool,
+    /// Reject tokens whose `nbf` is in the future.
+    pub validate_nbf: bool,
+    /// Reject tokens whose `iat` is in the future.
+    pub validate_iat: bool,
+}
+
+impl Default for TokenValidation {
+    fn default() -> Self {
+        TokenValidation {
+            algorithms: None,
+            issuers: None,
+            audiences: None,
+            required_claims: Vec::new(),
+            leeway: 60,
+            validate_exp: true,
+            validate_nbf: true,
+            validate_iat: true,
+        }
+    }
+}
+
+impl TokenValidation {
+    /// Reject `alg` unless it is on the [`algorithms`](Self::algorithms) allow-list.
+    pub fn check_algorithm(&self, alg: &str) -> Result<(), USSOError> {
+        match &self.algorithms {
+            Some(allowed) if !allowed.iter().any(|a| a == alg) => {
+                Err(USSOError::AlgorithmNotAllowed(alg.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// This validation, allowing only `algorithm` if it has no allow-list of
+    /// its own and `algorithm` is not empty.
+    ///
+    /// Keeps configs that still set the deprecated `algorithm` field of
+    /// [`AuthConfig`] or [`JWTConfig`](crate::schemas::JWTConfig) enforced.
+    pub(crate) fn or_algorithm(&self, algorithm: &str) -> Cow<'_, TokenValidation> {
+        if self.algorithms.is_some() || algorithm.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        Cow::Owned(TokenValidation {
+            algorithms: Some(vec![algorithm.to_string()]),
+            ..self.clone()
+        })
+    }
+
+    /// Check the registered claims of a decoded token payload.
+    ///
+    /// Returns `USSOError::ExpiredToken` for an expired token and
+    /// `USSOError::InvalidClaim` (naming the failing check) for anything else.
+    pub fn validate(&self, claims: &serde_json::Value) -> Result<(), USSOError> {
+        for name in &self.required_claims {
+            if claims.get(name).is_none_or(|v| v.is_null()) {
+                return Err(ClaimError::Missing(name.clone()).into());
+            }
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let leeway = i64::try_from(self.leeway).unwrap_or(i64::MAX);
+        let numeric = |name: &str| -> Result<Option<i64>, USSOError> {
+            match claims.get(name) {
+                None | Some(serde_json::Value::Null) => Ok(None),
+                Some(value) => value
+                    .as_i64()
+                    .or_else(|| value.as_f64().map(|f| f as i64))
+                    .map(Some)
+                    .ok_or_else(|| ClaimError::Malformed(name.to_string()).into()),
+            }
+        };
+
+        if self.validate_exp {
+            if let Some(exp) = numeric("exp")? {
+                if exp.saturating_add(leeway) < now {
+                    return Err(USSOError::ExpiredToken);
+                }
+            }
+        }
+        if self.validate_nbf {
+            if let Some(nbf) = numeric("nbf")? {
+                if nbf.saturating_sub(leeway) > now {
+                    return Err(ClaimError::NotYetValid.into());
+                }
+            }
+        }
+        if self.validate_iat {
+            if let Some(iat) = numeric("iat")? {
+                if iat.saturating_sub(leeway) > now {
+                    return Err(ClaimError::IssuedInFuture.into());
+                }
+            }
+        }
+
+        if let Some(issuers) = &self.issuers {
+            let iss = match claims.get("iss") {
+                Some(serde_json::Value::String(iss)) => iss,
+                Some(_) => return Err(ClaimError::Malformed("iss".into()).into()),
+                None => return Err(ClaimError::Missing("iss".into()).into()),
+            };
+            if !issuers.iter().any(|expected| expected == iss) {
+                return Err(ClaimError::InvalidIssuer(iss.clone()).into());
+            }
+        }
+
+        if let Some(audiences) = &self.audiences {
+            let token_audiences: Vec<&str> = match claims.get("aud") {
+                Some(serde_json::Value::String(aud)) => vec![aud.as_str()],
+                Some(serde_json::Value::Array(values)) => {
+                    values.iter().filter_map(|v| v.as_str()).collect()
+                }
+                Some(_) => return Err(ClaimError::Malformed("aud".into()).into()),
+                None => return Err(ClaimError::Missing("aud".into()).into()),
+            };
+            if !token_audiences
+                .iter()
+                .any(|aud| audiences.iter().any(|expected| expected == aud))
+            {
+                return Err(ClaimError::InvalidAudience.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Shared secret for verifying HMAC-signed (HS256/HS384/HS512) tokens.
+///
+/// Meant for legacy internal service tokens; its `Debug` output is redacted.
+///
+/// # Example
+///
+/// ```rust
+/// use usso::config::{AuthConfig, HmacSecret};
+///
+/// let mut config = AuthConfig::default();
+/// config.hmac_secret = Some(HmacSecret::new("internal-shared-secret"));
+/// config.validation.algorithms = Some(vec!["HS256".into()]);
+/// ```
+#[derive(Clone)]
+pub struct HmacSecret(Vec<u8>);
+
+impl HmacSecret {
+    /// Wrap the raw secret bytes.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        HmacSecret(secret.into())
+    }
+
+    /// The raw secret bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for HmacSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HmacSecret(..)")
+    }
+}
+
+/// How [`UssoAuth`](crate::core::UssoAuth) validates tokens for an [`AuthConfig`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Verify JWTs locally with the configured keys.
+    #[default]
+    LocalJwt,
+    /// Ask the [introspection endpoint](AuthConfig::introspection) about every
+    /// token, including opaque ones.
+    Introspect,
+    /// Verify locally, and introspect tokens that local verification cannot
+    /// decide on: opaque tokens, unknown `kid`s, unsupported algorithms, or an
+    /// unreachable JWKS. Tokens that fail locally (bad signature, expired,
+    /// wrong audience, ...) are rejected without introspection.
+    LocalThenIntrospect,
+}
+
 /// Top-level authentication configuration.
 ///
-/// Combines JWKS URL, API key header config, JWT header config, and the
-/// signing algorithm into a single structure used by [`UssoAuth`](crate::core::UssoAuth).
+/// Combines the key source (a JWKS URL and/or inline keys), API key header
+/// config, JWT header config and the [`TokenValidation`] rules into a single
+/// structure used by [`UssoAuth`](crate::core::UssoAuth).
 ///
 /// # Example
 ///
@@ -109,23 +339,52 @@ impl APIHeaderConfig {
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
     pub jwks_url: Option<String>,
+    /// Inline keys, looked up before [`jwks_url`](Self::jwks_url); see
+    /// [`jwks::local`](crate::jwks::local) to load them from files or the environment.
+    pub keys: Option<Jwks>,
+    /// Shared secret for HS256/HS384/HS512 tokens, used whatever their `kid`.
+    pub hmac_secret: Option<HmacSecret>,
     pub api_key_header: Option<APIHeaderConfig>,
     pub jwt_header: Option<HeaderConfig>,
+    /// The only accepted `alg` when [`TokenValidation::algorithms`] is
+    /// `None`; empty (the default) accepts every supported algorithm.
+    #[deprecated(note = "set `validation.algorithms`, which takes precedence, instead")]
     pub algorithm: String,
+    pub validation: TokenValidation,
+    /// Trust anchors for keys published with an `x5c` certificate chain.
+    pub x509: Option<X509Validation>,
+    /// How tokens are validated: locally, by introspection, or both.
+    pub validation_mode: ValidationMode,
+    /// Client used by the introspection [`validation_mode`](Self::validation_mode)s.
+    pub introspection: Option<Arc<IntrospectionClient>>,
 }
 
 impl Default for AuthConfig {
+    #[allow(deprecated)]
     fn default() -> Self {
         AuthConfig {
             jwks_url: None,
+            keys: None,
+            hmac_secret: None,
             api_key_header: Some(APIHeaderConfig::default()),
             jwt_header: Some(HeaderConfig::default()),
-            algorithm: "RS256".to_string(),
+            algorithm: String::new(),
+            validation: TokenValidation::default(),
+            x509: None,
+            validation_mode: ValidationMode::default(),
+            introspection: None,
         }
     }
 }
 
 impl AuthConfig {
+    /// The [`validation`](Self::validation) rules, with the allow-list taken
+    /// from the deprecated [`algorithm`](Self::algorithm) field if unset.
+    #[allow(deprecated)]
+    pub(crate) fn token_validation(&self) -> Cow<'_, TokenValidation> {
+        self.validation.or_algorithm(&self.algorithm)
+    }
+
     /// Create a new `AuthConfig` with the given JWKS URL and defaults for everything else.
     pub fn new(jwks_url: Option<String>) -> Self {
         AuthConfig {
@@ -134,6 +393,15 @@ impl AuthConfig {
         }
     }
 
+    /// Create a new `AuthConfig` that verifies tokens with `keys` only, without
+    /// any HTTP.
+    pub fn from_keys(keys: Jwks) -> Self {
+        AuthConfig {
+            keys: Some(keys),
+            ..Default::default()
+        }
+    }
+
     /// Extract a JWT from headers or cookies using the configured [`HeaderConfig`].
     pub fn get_jwt(&self, headers: &HashMap<String, String>, cookies: &HashMap<String, String>) -> Option<String> {
         self.jwt_header.as_ref().and_then(|h| h.get_key(headers, cookies))
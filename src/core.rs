@@ -7,42 +7,170 @@
 //!
 //! Token verification auto-detects the algorithm from the JWT header:
 //! - **RSA**: RS256, RS384, RS512, PS256, PS384, PS512
-//! - **ECDSA**: ES256, ES384 (via `jsonwebtoken`), **ES512** (manual P-521)
-//! - **EdDSA**: Ed25519
+//! - **ECDSA**: ES256, ES384 (via `jsonwebtoken`), **ES512** (manual P-521),
+//!   **ES256K** (manual secp256k1)
+//! - **EdDSA**: Ed25519, **Ed448** (manual)
+//! - **HMAC**: HS256, HS384, HS512 with a shared secret
 //!
-//! RSA and EC keys are parsed from JWK `n`/`e` and `crv`/`x`/`y` respectively.
+//! RSA and EC keys are parsed from JWK `n`/`e` and `crv`/`x`/`y` respectively,
+//! OKP keys from `crv`/`x` and symmetric keys from `k`.
+//! The header `alg` is never trusted on its own: it must be allowed by the
+//! config's [`TokenValidation`] and match the selected key's `alg`, `kty` and
+//! `crv`, and the key's `use`, when present, must be `sig`.
+//!
+//! Encrypted (JWE) tokens are decrypted by [`UssoAuth`] with the keys given to
+//! [`UssoAuth::with_decryption_keys`]; see [`crate::jwe`].
 
 use base64::Engine;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
 
-use crate::config::AuthConfig;
-use crate::exceptions::USSOError;
-use crate::jwks::{fetch_jwks_sync, get_jwk_keys};
-use crate::schemas::{JWTConfig, Jwk, Jwks, UserData};
+use crate::config::{APIHeaderConfig, AuthConfig, TokenValidation, ValidationMode};
+use crate::discovery::ProviderMetadata;
+use crate::dpop::{DpopConfig, DpopVerifier};
+use crate::exceptions::{DiscoveryError, JwksError, USSOError};
+use crate::introspection::IntrospectionClient;
+use crate::issuers::IssuerRegistry;
+use crate::jwe::{self, DecryptionKey};
+use crate::jwks::cache::JwksCache;
+use crate::jwks::global_cache;
+use crate::revocation::RevocationChecker;
+use crate::schemas::{JWTConfig, Jwk, Jwks, JwtHeader, UserData};
+use crate::step_up::StepUpRequirement;
 
 /// Decode and validate a JWT, auto-detecting the algorithm from the token header.
 ///
 /// Supports RSA (RS256/RS384/RS512/PS256/PS384/PS512), EC (ES256/ES384),
-/// EdDSA via `jsonwebtoken` and ES512 via manual ECDSA P-521 verification.
+/// Ed25519 and HMAC (HS256/HS384/HS512) via `jsonwebtoken`, and ES512,
+/// ES256K and Ed448 via manual verification.
+/// Registered claims are checked with [`TokenValidation::default`] (`exp`,
+/// `nbf` and `iat` with a 60 second leeway).
+///
+/// Use [`decode_claims`] to deserialize into a custom claims type.
 pub fn decode_token(key: &Jwk, token: &str) -> Result<UserData, USSOError> {
-    let header = crate::schemas::JwtHeader::from_token(token)
-        .map_err(|_| USSOError::InvalidToken)?;
+    decode_token_with_validation(key, token, &TokenValidation::default())
+}
+
+/// Decode a JWT like [`decode_token`] and enforce `validation` on it.
+///
+/// Before verifying the signature, the token `alg` must be on the
+/// [allow-list](TokenValidation::algorithms) and compatible with the key
+/// (see [`check_key_for_algorithm`]). The same claim checks (`exp`, `nbf`,
+/// `iat`, `iss`, `aud`, required claims) then apply to every algorithm.
+pub fn decode_token_with_validation(
+    key: &Jwk,
+    token: &str,
+    validation: &TokenValidation,
+) -> Result<UserData, USSOError> {
+    decode_claims(key, token, validation)
+}
+
+/// Decode a JWT like [`decode_token_with_validation`], deserializing the
+/// verified payload into a caller-provided claims type.
+///
+/// `C` is typically [`UserData<Extra>`](UserData) with service-specific
+/// extra claims, but any [`DeserializeOwned`] type works.
+pub fn decode_claims<C: DeserializeOwned>(
+    key: &Jwk,
+    token: &str,
+    validation: &TokenValidation,
+) -> Result<C, USSOError> {
+    let claims = decode_claims_value(key, token, validation)?;
+    deserialize_claims(claims)
+}
+
+fn deserialize_claims<C: DeserializeOwned>(claims: serde_json::Value) -> Result<C, USSOError> {
+    serde_json::from_value(claims)
+        .map_err(|err| USSOError::MalformedToken(format!("unexpected claims: {err}")))
+}
+
+/// Verify the token and its claims, returning the raw payload.
+fn decode_claims_value(
+    key: &Jwk,
+    token: &str,
+    validation: &TokenValidation,
+) -> Result<serde_json::Value, USSOError> {
+    let header = JwtHeader::from_token(token)?;
+    validation.check_algorithm(&header.alg)?;
+    let key = crate::jwks::x509::resolve_key(key)?;
+    check_key_for_algorithm(&key, &header.alg)?;
+    let claims = verify_token(&key, token, &header.alg)?;
+    validation.validate(&claims)?;
+    Ok(claims)
+}
+
+/// Check that `key` may verify a token signed with `alg`.
+///
+/// Rejects keys whose `use` is set to anything but `sig`, whose declared `alg`
+/// differs from the token's, or whose `kty`/`crv` does not fit the algorithm,
+/// closing algorithm-confusion attacks where the token header picks the algorithm.
+pub fn check_key_for_algorithm(key: &Jwk, alg: &str) -> Result<(), USSOError> {
+    if let Some(key_use) = key.r#use.as_deref().filter(|key_use| *key_use != "sig") {
+        return Err(USSOError::KeyMismatch(format!(
+            "key {} is not a signing key (use: {})",
+            key.kid, key_use
+        )));
+    }
+    if let Some(key_alg) = key.alg.as_deref() {
+        if key_alg != alg {
+            return Err(USSOError::KeyMismatch(format!(
+                "key {} is for {}, token uses {}",
+                key.kid, key_alg, alg
+            )));
+        }
+    }
+    let (kty, curves): (&str, &[&str]) = match alg {
+        "RS256" | "RS384" | "RS512" | "PS256" | "PS384" | "PS512" => ("RSA", &[]),
+        "ES256" => ("EC", &["P-256"]),
+        "ES384" => ("EC", &["P-384"]),
+        "ES512" => ("EC", &["P-521"]),
+        "ES256K" => ("EC", &["secp256k1"]),
+        "EdDSA" => ("OKP", &["Ed25519", "Ed448"]),
+        "HS256" | "HS384" | "HS512" => ("oct", &[]),
+        _ => return Err(USSOError::UnsupportedAlgorithm(alg.to_string())),
+    };
+    let crv_fits = curves.is_empty() || key.crv.as_deref().is_some_and(|crv| curves.contains(&crv));
+    if key.kty != kty || !crv_fits {
+        return Err(USSOError::KeyMismatch(format!(
+            "key {} ({} {}) cannot verify {}",
+            key.kid,
+            key.kty,
+            key.crv.as_deref().unwrap_or("-"),
+            alg
+        )));
+    }
+    Ok(())
+}
 
-    match header.alg.as_str() {
-        "ES512" => decode_es512_token(key, token),
+/// Verify the token signature and return its raw claims.
+pub(crate) fn verify_token(key: &Jwk, token: &str, alg: &str) -> Result<serde_json::Value, USSOError> {
+    match alg {
+        "ES512" => verify_es512_token(key, token),
         alg if matches!(
             alg,
             "RS256" | "RS384" | "RS512" | "PS256" | "PS384" | "PS512"
-        ) => decode_rsa_token(key, token, alg),
-        alg if matches!(alg, "ES256" | "ES384") => decode_ec_token(key, token, alg),
-        "EdDSA" => decode_eddsa_token(key, token),
-        _ => Err(USSOError::InvalidToken),
+        ) => verify_rsa_token(key, token, alg),
+        alg if matches!(alg, "ES256" | "ES384") => verify_ec_token(key, token, alg),
+        "ES256K" => verify_es256k_token(key, token),
+        "EdDSA" if key.crv.as_deref() == Some("Ed448") => verify_ed448_token(key, token),
+        "EdDSA" => verify_eddsa_token(key, token),
+        alg if matches!(alg, "HS256" | "HS384" | "HS512") => verify_hmac_token(key, token, alg),
+        _ => Err(USSOError::UnsupportedAlgorithm(alg.to_string())),
     }
 }
 
+/// Signing algorithms that tokens can be verified with.
+pub const SUPPORTED_ALGORITHMS: &[&str] = &[
+    "RS256", "RS384", "RS512", "PS256", "PS384", "PS512", "ES256", "ES384", "ES512", "ES256K",
+    "EdDSA", "HS256", "HS384", "HS512",
+];
+
 fn algorithm_from_str(s: &str) -> Option<Algorithm> {
     match s {
         "RS256" => Some(Algorithm::RS256),
@@ -54,126 +182,176 @@ fn algorithm_from_str(s: &str) -> Option<Algorithm> {
         "ES256" => Some(Algorithm::ES256),
         "ES384" => Some(Algorithm::ES384),
         "EdDSA" => Some(Algorithm::EdDSA),
+        "HS256" => Some(Algorithm::HS256),
+        "HS384" => Some(Algorithm::HS384),
+        "HS512" => Some(Algorithm::HS512),
         _ => None,
     }
 }
 
-fn decode_rsa_token(key: &Jwk, token: &str, alg: &str) -> Result<UserData, USSOError> {
-    let n = key.n.as_deref().ok_or(USSOError::InvalidToken)?;
-    let e = key.e.as_deref().ok_or(USSOError::InvalidToken)?;
+/// Verify the signature only; claims are checked by [`TokenValidation`].
+fn verify_with_jsonwebtoken(
+    token: &str,
+    decoding_key: &DecodingKey,
+    alg: Algorithm,
+) -> Result<serde_json::Value, USSOError> {
+    let mut validation = Validation::new(alg);
+    validation.algorithms = vec![alg];
+    validation.required_spec_claims.clear();
+    validation.validate_exp = false;
+    validation.validate_nbf = false;
+    validation.validate_aud = false;
+    decode::<serde_json::Value>(token, decoding_key, &validation)
+        .map(|token_data| token_data.claims)
+        .map_err(jsonwebtoken_error_to_usso)
+}
+
+fn jsonwebtoken_error_to_usso(err: jsonwebtoken::errors::Error) -> USSOError {
+    use jsonwebtoken::errors::ErrorKind;
+
+    match err.kind() {
+        ErrorKind::InvalidSignature => USSOError::InvalidSignature,
+        ErrorKind::InvalidAlgorithm | ErrorKind::InvalidAlgorithmName => {
+            USSOError::UnsupportedAlgorithm(err.to_string())
+        }
+        ErrorKind::InvalidKeyFormat
+        | ErrorKind::InvalidEcdsaKey
+        | ErrorKind::InvalidRsaKey(_)
+        | ErrorKind::RsaFailedSigning => USSOError::InvalidKey(err.to_string()),
+        _ => USSOError::MalformedToken(err.to_string()),
+    }
+}
+
+fn key_param<'a>(key: &'a Jwk, value: &'a Option<String>, name: &str) -> Result<&'a str, USSOError> {
+    value
+        .as_deref()
+        .ok_or_else(|| USSOError::InvalidKey(format!("key {} has no `{}`", key.kid, name)))
+}
+
+fn decode_key_param(key: &Jwk, value: &Option<String>, name: &str) -> Result<Vec<u8>, USSOError> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(key_param(key, value, name)?)
+        .map_err(|_| USSOError::InvalidKey(format!("key {} has a malformed `{}`", key.kid, name)))
+}
+
+fn decode_segment(segment: &str, name: &str) -> Result<Vec<u8>, USSOError> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| USSOError::MalformedToken(format!("{name} is not valid base64url")))
+}
+
+fn verify_rsa_token(key: &Jwk, token: &str, alg: &str) -> Result<serde_json::Value, USSOError> {
+    let n = key_param(key, &key.n, "n")?;
+    let e = key_param(key, &key.e, "e")?;
     let decoding_key =
-        DecodingKey::from_rsa_components(n, e).map_err(|_| USSOError::InvalidToken)?;
-    let jsonwebtoken_alg = algorithm_from_str(alg).ok_or(USSOError::InvalidToken)?;
-    let mut validation = Validation::new(jsonwebtoken_alg);
-    validation.algorithms = vec![jsonwebtoken_alg];
-    match decode::<UserData>(token, &decoding_key, &validation) {
-        Ok(token_data) => Ok(token_data.claims),
-        Err(err) => match err.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => Err(USSOError::ExpiredToken),
-            _ => Err(USSOError::InvalidToken),
-        },
-    }
-}
-
-fn decode_ec_token(key: &Jwk, token: &str, alg: &str) -> Result<UserData, USSOError> {
-    let x_b64 = key.x.as_deref().ok_or(USSOError::InvalidToken)?;
-    let y_b64 = key.y.as_deref().ok_or(USSOError::InvalidToken)?;
-    let x = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(x_b64)
-        .map_err(|_| USSOError::InvalidToken)?;
-    let y = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(y_b64)
-        .map_err(|_| USSOError::InvalidToken)?;
+        DecodingKey::from_rsa_components(n, e).map_err(jsonwebtoken_error_to_usso)?;
+    let jsonwebtoken_alg = algorithm_from_str(alg)
+        .ok_or_else(|| USSOError::UnsupportedAlgorithm(alg.to_string()))?;
+    verify_with_jsonwebtoken(token, &decoding_key, jsonwebtoken_alg)
+}
+
+fn verify_ec_token(key: &Jwk, token: &str, alg: &str) -> Result<serde_json::Value, USSOError> {
+    let x = decode_key_param(key, &key.x, "x")?;
+    let y = decode_key_param(key, &key.y, "y")?;
     let mut encoded = vec![0x04u8];
     encoded.extend_from_slice(&x);
     encoded.extend_from_slice(&y);
     let decoding_key = DecodingKey::from_ec_der(&encoded);
-    let jsonwebtoken_alg = algorithm_from_str(alg).ok_or(USSOError::InvalidToken)?;
-    let mut validation = Validation::new(jsonwebtoken_alg);
-    validation.algorithms = vec![jsonwebtoken_alg];
-    match decode::<UserData>(token, &decoding_key, &validation) {
-        Ok(token_data) => Ok(token_data.claims),
-        Err(err) => match err.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => Err(USSOError::ExpiredToken),
-            _ => Err(USSOError::InvalidToken),
-        },
-    }
-}
-
-fn decode_eddsa_token(key: &Jwk, token: &str) -> Result<UserData, USSOError> {
-    let x_b64 = key.x.as_deref().ok_or(USSOError::InvalidToken)?;
-    let x = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(x_b64)
-        .map_err(|_| USSOError::InvalidToken)?;
+    let jsonwebtoken_alg = algorithm_from_str(alg)
+        .ok_or_else(|| USSOError::UnsupportedAlgorithm(alg.to_string()))?;
+    verify_with_jsonwebtoken(token, &decoding_key, jsonwebtoken_alg)
+}
+
+fn verify_eddsa_token(key: &Jwk, token: &str) -> Result<serde_json::Value, USSOError> {
+    let x = decode_key_param(key, &key.x, "x")?;
     let decoding_key = DecodingKey::from_ed_der(&x);
-    let mut validation = Validation::new(Algorithm::EdDSA);
-    validation.algorithms = vec![Algorithm::EdDSA];
-    match decode::<UserData>(token, &decoding_key, &validation) {
-        Ok(token_data) => Ok(token_data.claims),
-        Err(err) => match err.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => Err(USSOError::ExpiredToken),
-            _ => Err(USSOError::InvalidToken),
-        },
-    }
+    verify_with_jsonwebtoken(token, &decoding_key, Algorithm::EdDSA)
 }
 
-fn decode_es512_token(key: &Jwk, token: &str) -> Result<UserData, USSOError> {
-    use p521::ecdsa::{Signature, VerifyingKey};
-    use p521::ecdsa::signature::Verifier;
+fn verify_hmac_token(key: &Jwk, token: &str, alg: &str) -> Result<serde_json::Value, USSOError> {
+    let secret = decode_key_param(key, &key.k, "k")?;
+    let jsonwebtoken_alg = algorithm_from_str(alg)
+        .ok_or_else(|| USSOError::UnsupportedAlgorithm(alg.to_string()))?;
+    verify_with_jsonwebtoken(token, &DecodingKey::from_secret(&secret), jsonwebtoken_alg)
+}
 
+/// Split a compact JWS into its signing input, decoded signature and payload segment.
+fn split_jws(token: &str) -> Result<(&str, Vec<u8>, &str), USSOError> {
     let parts: Vec<&str> = token.split('.').collect();
     if parts.len() != 3 {
-        return Err(USSOError::InvalidToken);
+        return Err(crate::exceptions::JwtError::InvalidFormat.into());
     }
+    let signing_input = &token[..parts[0].len() + 1 + parts[1].len()];
+    Ok((signing_input, decode_segment(parts[2], "signature")?, parts[1]))
+}
 
-    let sig_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(parts[2])
-        .map_err(|_| USSOError::InvalidToken)?;
-    let signature = Signature::from_slice(&sig_bytes).map_err(|_| USSOError::InvalidToken)?;
-
-    let x_b64 = key.x.as_deref().ok_or(USSOError::InvalidToken)?;
-    let y_b64 = key.y.as_deref().ok_or(USSOError::InvalidToken)?;
-    let x = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(x_b64)
-        .map_err(|_| USSOError::InvalidToken)?;
-    let y = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(y_b64)
-        .map_err(|_| USSOError::InvalidToken)?;
+fn decode_payload(segment: &str) -> Result<serde_json::Value, USSOError> {
+    let payload_bytes = decode_segment(segment, "payload")?;
+    serde_json::from_slice(&payload_bytes)
+        .map_err(|_| USSOError::MalformedToken("payload is not a JSON object".into()))
+}
 
+/// The uncompressed SEC1 point (`0x04 || x || y`) of an EC key.
+fn ec_point(key: &Jwk) -> Result<Vec<u8>, USSOError> {
+    let x = decode_key_param(key, &key.x, "x")?;
+    let y = decode_key_param(key, &key.y, "y")?;
     let mut encoded_point = vec![0x04u8];
     encoded_point.extend_from_slice(&x);
     encoded_point.extend_from_slice(&y);
+    Ok(encoded_point)
+}
 
-    let verifying_key =
-        VerifyingKey::from_sec1_bytes(&encoded_point).map_err(|_| USSOError::InvalidToken)?;
+fn verify_es512_token(key: &Jwk, token: &str) -> Result<serde_json::Value, USSOError> {
+    use p521::ecdsa::{Signature, VerifyingKey};
+    use p521::ecdsa::signature::Verifier;
 
-    let message = format!("{}.{}", parts[0], parts[1]);
+    let (message, sig_bytes, payload) = split_jws(token)?;
+    let signature = Signature::from_slice(&sig_bytes).map_err(|_| USSOError::InvalidSignature)?;
+    let verifying_key = VerifyingKey::from_sec1_bytes(&ec_point(key)?)
+        .map_err(|_| USSOError::InvalidKey(format!("key {} is not a P-521 point", key.kid)))?;
     verifying_key
         .verify(message.as_bytes(), &signature)
-        .map_err(|_| USSOError::InvalidToken)?;
-
-    // Check expiration
-    let payload_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(parts[1])
-        .map_err(|_| USSOError::InvalidToken)?;
-    let claims: HashMap<String, serde_json::Value> =
-        serde_json::from_slice(&payload_bytes).map_err(|_| USSOError::InvalidToken)?;
-    if let Some(exp) = claims.get("exp").and_then(|v| v.as_i64()) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-        if exp < now {
-            return Err(USSOError::ExpiredToken);
-        }
-    }
+        .map_err(|_| USSOError::InvalidSignature)?;
+    decode_payload(payload)
+}
+
+fn verify_es256k_token(key: &Jwk, token: &str) -> Result<serde_json::Value, USSOError> {
+    use k256::ecdsa::{Signature, VerifyingKey};
+    use k256::ecdsa::signature::Verifier;
 
-    let user_data: UserData =
-        serde_json::from_slice(&payload_bytes).map_err(|_| USSOError::InvalidToken)?;
-    Ok(user_data)
+    let (message, sig_bytes, payload) = split_jws(token)?;
+    let signature = Signature::from_slice(&sig_bytes).map_err(|_| USSOError::InvalidSignature)?;
+    // k256 only accepts low-S signatures; JWS signers are not required to normalize.
+    let signature = signature.normalize_s().unwrap_or(signature);
+    let verifying_key = VerifyingKey::from_sec1_bytes(&ec_point(key)?)
+        .map_err(|_| USSOError::InvalidKey(format!("key {} is not a secp256k1 point", key.kid)))?;
+    verifying_key
+        .verify(message.as_bytes(), &signature)
+        .map_err(|_| USSOError::InvalidSignature)?;
+    decode_payload(payload)
 }
 
+fn verify_ed448_token(key: &Jwk, token: &str) -> Result<serde_json::Value, USSOError> {
+    use ed448_goldilocks_plus::{Signature, VerifyingKey};
 
+    let (message, sig_bytes, payload) = split_jws(token)?;
+    let signature = sig_bytes
+        .as_slice()
+        .try_into()
+        .ok()
+        .and_then(|bytes| Signature::from_bytes(bytes).ok())
+        .ok_or(USSOError::InvalidSignature)?;
+    let verifying_key = decode_key_param(key, &key.x, "x")?
+        .as_slice()
+        .try_into()
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_bytes(bytes).ok())
+        .ok_or_else(|| USSOError::InvalidKey(format!("key {} is not an Ed448 key", key.kid)))?;
+    verifying_key
+        .verify_raw(&signature, message.as_bytes())
+        .map_err(|_| USSOError::InvalidSignature)?;
+    decode_payload(payload)
+}
 
 /// Check whether a JWT has expired by inspecting its `exp` claim.
 ///
@@ -181,13 +359,11 @@ fn decode_es512_token(key: &Jwk, token: &str) -> Result<UserData, USSOError> {
 pub fn is_expired(token: &str) -> Result<bool, USSOError> {
     let parts: Vec<&str> = token.split('.').collect();
     if parts.len() != 3 {
-        return Err(USSOError::InvalidToken);
+        return Err(crate::exceptions::JwtError::InvalidFormat.into());
     }
-    let payload_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(parts[1])
-        .map_err(|_| USSOError::InvalidToken)?;
+    let payload_bytes = decode_segment(parts[1], "payload")?;
     let claims: HashMap<String, serde_json::Value> = serde_json::from_slice(&payload_bytes)
-        .map_err(|_| USSOError::InvalidToken)?;
+        .map_err(|_| USSOError::MalformedToken("payload is not a JSON object".into()))?;
 
     let exp = claims
         .get("exp")
@@ -207,19 +383,154 @@ pub fn is_expired(token: &str) -> Result<bool, USSOError> {
             .as_secs() as i64)
 }
 
-/// Decode a JWT using the globally cached JWKS key set.
+/// Decode a JWT using the globally cached JWKS key set for `jwk_url`.
+///
+/// The key set is taken from the [global cache](crate::jwks::global_cache),
+/// usually populated via [`init_jwks_sync`](crate::jwks::init_jwks_sync) or
+/// [`init_jwks_async`](crate::jwks::init_jwks_async). Missing or expired key
+/// sets are refetched with a blocking request, as is a key set that does not
+/// contain the token's `kid` (rate limited, see
+/// [`JwksCache::find_key_sync`](crate::jwks::cache::JwksCache::find_key_sync)).
+/// Prefer [`decode_token_with_jwks_async`] inside an async runtime.
+pub fn decode_token_with_jwks(jwk_url: &str, token: &str) -> Result<UserData, USSOError> {
+    let kid = token_kid(token)?;
+    let key = global_cache().find_key_sync(jwk_url, &kid)?;
+    decode_token(&key, token)
+}
+
+/// Async variant of [`decode_token_with_jwks`].
+///
+/// Missing or expired key sets are refetched without blocking the runtime.
+pub async fn decode_token_with_jwks_async(
+    jwk_url: &str,
+    token: &str,
+) -> Result<UserData, USSOError> {
+    let kid = token_kid(token)?;
+    let key = global_cache().find_key_async(jwk_url, &kid).await?;
+    decode_token(&key, token)
+}
+
+fn token_kid(token: &str) -> Result<String, USSOError> {
+    let header = JwtHeader::from_token(token)?;
+    header
+        .kid
+        .ok_or_else(|| USSOError::MalformedToken("header has no kid".into()))
+}
+
+/// Whether the token's `token_type` (defaulting to `"access"`) is the expected one.
+fn token_type_matches(claims: &serde_json::Value, expected_token_type: Option<&str>) -> bool {
+    let token_type = claims
+        .get("token_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("access");
+    expected_token_type.is_none_or(|expected| token_type == expected)
+}
+
+/// The key `config` holds locally for a token with `header`: its HMAC secret
+/// for HS256/HS384/HS512 tokens (whatever their `kid`), or the inline key
+/// matching the `kid`.
 ///
-/// The JWKS cache must be initialized first via [`init_jwks_sync`](crate::jwks::init_jwks_sync)
-/// or [`init_jwks_async`](crate::jwks::init_jwks_async). The correct key is
-/// looked up by the `kid` header claim.
-pub fn decode_token_with_jwks(_jwk_url: &str, token: &str) -> Result<UserData, USSOError> {
-    let jwk_keys =
-        get_jwk_keys().map_err(|_| USSOError::Other("JWKS not initialized".to_string()))?;
-    let header =
-        crate::schemas::JwtHeader::from_token(token).map_err(|_| USSOError::InvalidToken)?;
-    let kid = header.kid.ok_or(USSOError::InvalidToken)?;
-    let key = jwk_keys.match_kid(&kid).ok_or(USSOError::InvalidToken)?;
-    decode_token(key, token)
+/// HMAC tokens are never verified with a `oct` key from a key set, inline or
+/// remote, so without a secret they fail with [`USSOError::AlgorithmNotAllowed`].
+fn local_key(config: &AuthConfig, header: &JwtHeader) -> Option<Result<Jwk, USSOError>> {
+    if header.alg.starts_with("HS") {
+        let kid = header.kid.clone().unwrap_or_default();
+        return Some(match &config.hmac_secret {
+            Some(secret) => Ok(Jwk::from_secret(kid, secret.as_bytes())),
+            None => Err(USSOError::AlgorithmNotAllowed(header.alg.clone())),
+        });
+    }
+    inline_key(config, header.kid.as_deref()?)
+}
+
+/// Look `kid` up in the inline keys of `config`.
+///
+/// Returns `None` when the config has no inline key for `kid` but can still
+/// fall back to its JWKS URL (or has no key source at all).
+fn inline_key(config: &AuthConfig, kid: &str) -> Option<Result<Jwk, USSOError>> {
+    let keys = config.keys.as_ref()?;
+    match keys.match_kid(kid) {
+        Some(key) => Some(Ok(key.clone())),
+        None if config.jwks_url.is_none() => Some(Err(USSOError::UnknownKey(kid.to_string()))),
+        None => None,
+    }
+}
+
+/// Keep the most informative of two verification failures.
+///
+/// When several configs are tried, a failure from a config that held the
+/// token's key (bad signature, expired, ...) explains the rejection better
+/// than another config not knowing the key or its JWKS being unreachable.
+fn most_relevant(current: Option<USSOError>, new: USSOError) -> USSOError {
+    let is_lookup =
+        |err: &USSOError| matches!(err, USSOError::UnknownKey(_) | USSOError::JwksUnavailable(_));
+    match current {
+        Some(current) if !is_lookup(&current) || is_lookup(&new) => current,
+        _ => new,
+    }
+}
+
+fn verify_with_key(
+    key: &Jwk,
+    token: &str,
+    config: &AuthConfig,
+) -> Result<serde_json::Value, USSOError> {
+    if let Some(x509) = &config.x509 {
+        x509.verify(key)?;
+    }
+    decode_claims_value(key, token, &config.token_validation())
+}
+
+/// Whether [`ValidationMode::LocalThenIntrospect`] should ask the
+/// introspection endpoint after local verification failed with `err`: only
+/// when the token could not be checked locally, never when it was rejected.
+fn needs_introspection(err: &USSOError) -> bool {
+    matches!(
+        err,
+        USSOError::MalformedToken(_)
+            | USSOError::UnknownKey(_)
+            | USSOError::JwksUnavailable(_)
+            | USSOError::UnsupportedAlgorithm(_)
+    )
+}
+
+fn introspection_client(config: &AuthConfig) -> Result<&IntrospectionClient, USSOError> {
+    config.introspection.as_deref().ok_or_else(|| {
+        USSOError::IntrospectionUnavailable("no introspection client configured".into())
+    })
+}
+
+/// Introspect `token` (blocking) and check the registered claims of the response.
+fn introspect_sync(token: &str, config: &AuthConfig) -> Result<serde_json::Value, USSOError> {
+    let claims = introspection_client(config)?.introspect_claims_sync(token)?;
+    config.validation.validate(&claims)?;
+    Ok(claims)
+}
+
+/// Async variant of [`introspect_sync`].
+async fn introspect_async(
+    token: &str,
+    config: &AuthConfig,
+) -> Result<serde_json::Value, USSOError> {
+    let claims = introspection_client(config)?.introspect_claims_async(token).await?;
+    config.validation.validate(&claims)?;
+    Ok(claims)
+}
+
+/// The `cnf.jkt` claim of a DPoP-bound token.
+fn bound_key_thumbprint(claims: &serde_json::Value) -> Option<&str> {
+    claims.get("cnf")?.get("jkt")?.as_str()
+}
+
+/// DPoP-bound tokens are useless without a proof of possession, so they must
+/// not be accepted as plain bearer tokens.
+fn reject_dpop_bound(claims: &serde_json::Value) -> Result<(), USSOError> {
+    match bound_key_thumbprint(claims) {
+        Some(_) => Err(USSOError::InvalidDpopProof(
+            "DPoP-bound token presented without a proof".into(),
+        )),
+        None => Ok(()),
+    }
 }
 
 /// A basic JWT authentication validator.
@@ -238,9 +549,13 @@ pub fn decode_token_with_jwks(_jwk_url: &str, token: &str) -> Result<UserData, U
 ///     Err(e) => eprintln!("Auth failed: {}", e),
 /// }
 /// ```
+///
+/// The claims type defaults to [`UserData`]; use [`with_claims`](Usso::with_claims)
+/// to decode into your own type instead.
 #[derive(Debug, Clone)]
-pub struct Usso {
+pub struct Usso<C = UserData> {
     jwt_configs: Vec<JWTConfig>,
+    _claims: PhantomData<fn() -> C>,
 }
 
 impl Usso {
@@ -251,7 +566,10 @@ impl Usso {
     /// - `key` — an inline [`Jwks`] key set (used if both above are `None`)
     pub fn new(jwt_config: Option<JWTConfig>, jwk_url: Option<String>, key: Option<Jwks>) -> Self {
         let jwt_configs = Self::initialize_configs(jwt_config, jwk_url, key);
-        Usso { jwt_configs }
+        Usso {
+            jwt_configs,
+            _claims: PhantomData,
+        }
     }
 
     fn initialize_configs(
@@ -262,26 +580,58 @@ impl Usso {
         if let Some(config) = jwt_config {
             vec![config]
         } else if let Some(url) = jwk_url {
-            let res = fetch_jwks_sync(url.as_str()).ok();
-            vec![JWTConfig::new(Some(url.clone()), res)]
+            // Warm the shared cache; `JWTConfig::decode` refetches on expiry.
+            let _ = global_cache().get_or_fetch_sync(&url);
+            vec![JWTConfig::new(Some(url), None)]
         } else if let Some(keyset) = key {
             vec![JWTConfig::new(None, Some(keyset))]
         } else {
             vec![]
         }
     }
+}
+
+impl<C> Usso<C> {
+    /// Switch the claims type tokens are decoded into.
+    ///
+    /// ```rust,no_run
+    /// use serde::Deserialize;
+    /// use usso::core::Usso;
+    /// use usso::schemas::UserData;
+    ///
+    /// #[derive(Deserialize, Debug, Clone)]
+    /// struct Extra {
+    ///     plan: String,
+    /// }
+    ///
+    /// let usso = Usso::new(None, Some("https://sso.usso.io/website/jwks.json".into()), None)
+    ///     .with_claims::<UserData<Extra>>();
+    /// let claims = usso.user_data_from_token("eyJ...").unwrap();
+    /// println!("{}", claims.extra.plan);
+    /// ```
+    pub fn with_claims<D>(self) -> Usso<D> {
+        Usso {
+            jwt_configs: self.jwt_configs,
+            _claims: PhantomData,
+        }
+    }
+}
 
+impl<C: DeserializeOwned> Usso<C> {
     /// Attempt to decode and validate a JWT against all configured key sources.
     ///
-    /// Returns the first successfully decoded [`UserData`], or `USSOError::Unauthorized`
-    /// if no config can validate the token.
-    pub fn user_data_from_token(&self, token: &str) -> Result<UserData, USSOError> {
+    /// Returns the first successfully decoded claims. If no config can
+    /// validate the token, the most relevant failure is returned (see
+    /// [`USSOError`]), or `USSOError::Unauthorized` when there is no config.
+    pub fn user_data_from_token(&self, token: &str) -> Result<C, USSOError> {
+        let mut error = None;
         for config in &self.jwt_configs {
-            if let Ok(user_data) = config.decode(token) {
-                return Ok(user_data);
+            match config.decode_claims(token) {
+                Ok(user_data) => return Ok(user_data),
+                Err(err) => error = Some(most_relevant(error, err)),
             }
         }
-        Err(USSOError::Unauthorized)
+        Err(error.unwrap_or(USSOError::Unauthorized))
     }
 }
 
@@ -298,10 +648,21 @@ impl Usso {
 /// let auth = UssoAuth::new(None, Some("https://sso.usso.io".into()));
 /// let user = auth.user_data_from_token("eyJ...", Some("access"));
 /// ```
+///
+/// Like [`Usso`], the claims type defaults to [`UserData`] and can be changed
+/// with [`with_claims`](UssoAuth::with_claims).
 #[derive(Debug, Clone)]
-pub struct UssoAuth {
+pub struct UssoAuth<C = UserData> {
     jwt_configs: Vec<AuthConfig>,
     from_usso_base_url: Option<String>,
+    jwks_cache: Arc<JwksCache>,
+    http_client: reqwest::Client,
+    decryption_keys: Arc<Vec<DecryptionKey>>,
+    provider_metadata: Option<Arc<ProviderMetadata>>,
+    issuers: Arc<IssuerRegistry>,
+    revocation: Option<Arc<dyn RevocationChecker>>,
+    dpop: Arc<DpopVerifier>,
+    _claims: PhantomData<fn() -> C>,
 }
 
 impl UssoAuth {
@@ -309,6 +670,8 @@ impl UssoAuth {
     ///
     /// If `jwt_config` is `None`, a default [`AuthConfig`] is derived from
     /// `from_usso_base_url` (the JWKS URL becomes `{base}/.well-known/jwks.json`).
+    /// The manager owns a [`JwksCache`], so key sets are fetched once per
+    /// `jwks_url` and then served from memory until they expire.
     pub fn new(jwt_config: Option<AuthConfig>, from_usso_base_url: Option<String>) -> Self {
         let jwt_configs = match jwt_config {
             Some(config) => vec![config],
@@ -321,57 +684,416 @@ impl UssoAuth {
         UssoAuth {
             jwt_configs,
             from_usso_base_url,
+            jwks_cache: Arc::new(JwksCache::default()),
+            http_client: reqwest::Client::new(),
+            decryption_keys: Arc::new(Vec::new()),
+            provider_metadata: None,
+            issuers: Arc::new(IssuerRegistry::new()),
+            revocation: None,
+            dpop: Arc::new(DpopVerifier::default()),
+            _claims: PhantomData,
+        }
+    }
+
+    /// Create a manager configured from the OpenID Connect discovery document
+    /// at `{base_url}/.well-known/openid-configuration` (blocking).
+    ///
+    /// Keys are taken from the advertised `jwks_uri`, tokens must carry the
+    /// advertised `issuer`, and the advertised introspection and API key
+    /// endpoints are used (see [`ProviderMetadata::auth_config`]). The
+    /// remaining endpoints are available through
+    /// [`provider_metadata`](UssoAuth::provider_metadata).
+    ///
+    /// ```rust,no_run
+    /// use usso::core::UssoAuth;
+    ///
+    /// let auth = UssoAuth::from_discovery_sync("https://sso.usso.io").unwrap();
+    /// let user = auth.user_data_from_token("eyJ...", Some("access"));
+    /// ```
+    pub fn from_discovery_sync(base_url: &str) -> Result<Self, DiscoveryError> {
+        let metadata = ProviderMetadata::fetch_sync(base_url)?;
+        Ok(Self::from_provider_metadata(base_url, metadata))
+    }
+
+    /// Async variant of [`from_discovery_sync`](Self::from_discovery_sync).
+    pub async fn from_discovery_async(base_url: &str) -> Result<Self, DiscoveryError> {
+        let metadata = ProviderMetadata::fetch_async(base_url).await?;
+        Ok(Self::from_provider_metadata(base_url, metadata))
+    }
+
+    /// Create a manager from already fetched provider metadata.
+    pub fn from_provider_metadata(base_url: &str, metadata: ProviderMetadata) -> Self {
+        let mut auth = Self::new(
+            Some(metadata.auth_config()),
+            Some(base_url.trim_end_matches('/').to_string()),
+        );
+        auth.provider_metadata = Some(Arc::new(metadata));
+        auth
+    }
+
+    /// Heuristically detect the type of a compact token.
+    ///
+    /// - 3 dot-separated parts → `"jwt"`
+    /// - 5 dot-separated parts → `"jwe"`
+    /// - anything else → `None`
+    pub fn detect_compact_token_type(token: &str) -> Option<&'static str> {
+        let token = token.trim();
+        let parts: Vec<&str> = token.split('.').collect();
+        if parts.len() == 3 {
+            Some("jwt")
+        } else if parts.len() == 5 {
+            Some("jwe")
+        } else {
+            None
         }
     }
+}
 
+impl<C> UssoAuth<C> {
+    /// Switch the claims type tokens and API key responses are decoded into.
+    pub fn with_claims<D>(self) -> UssoAuth<D> {
+        UssoAuth {
+            jwt_configs: self.jwt_configs,
+            from_usso_base_url: self.from_usso_base_url,
+            jwks_cache: self.jwks_cache,
+            http_client: self.http_client,
+            decryption_keys: self.decryption_keys,
+            provider_metadata: self.provider_metadata,
+            issuers: self.issuers,
+            revocation: self.revocation,
+            dpop: self.dpop,
+            _claims: PhantomData,
+        }
+    }
+
+    /// Use `cache` for key lookups instead of the manager's own cache.
+    ///
+    /// Handy to share keys with a [`JwksRefresher`](crate::jwks::refresher::JwksRefresher)
+    /// or between several managers.
+    pub fn with_jwks_cache(mut self, cache: Arc<JwksCache>) -> Self {
+        self.jwks_cache = cache;
+        self
+    }
+
+    /// Private keys used to decrypt encrypted (JWE) tokens.
+    ///
+    /// Compact JWE tokens are decrypted with these keys and the nested JWS is
+    /// then verified like any other token. Without keys, JWE tokens are rejected.
+    pub fn with_decryption_keys(mut self, keys: Vec<DecryptionKey>) -> Self {
+        self.decryption_keys = Arc::new(keys);
+        self
+    }
+
+    /// Return the nested JWS of an encrypted token, or the token itself.
+    fn inner_jws<'t>(&self, token: &'t str) -> Result<Cow<'t, str>, USSOError> {
+        if !jwe::is_jwe(token) {
+            return Ok(Cow::Borrowed(token));
+        }
+        if self.decryption_keys.is_empty() {
+            return Err(USSOError::DecryptionFailed(
+                "no decryption keys configured".into(),
+            ));
+        }
+        jwe::decrypt_to_jws(token, &self.decryption_keys).map(Cow::Owned)
+    }
+
+    /// Route tokens to per-tenant configs by issuer instead of trying the
+    /// manager's own configs in turn.
+    ///
+    /// Once a non-empty registry is set, a token is only verified with the
+    /// config registered for its `iss` (or header `host`), and tokens from
+    /// unregistered issuers are rejected with `USSOError::UnknownIssuer`.
+    pub fn with_issuers(mut self, issuers: IssuerRegistry) -> Self {
+        self.issuers = Arc::new(issuers);
+        self
+    }
+
+    /// Reject verified tokens that `checker` reports as revoked, by their
+    /// `jti` or `session_id` claim.
+    ///
+    /// See [`crate::revocation`] for the in-memory and polling implementations.
+    pub fn with_revocation_checker(mut self, checker: impl RevocationChecker + 'static) -> Self {
+        self.revocation = Some(Arc::new(checker));
+        self
+    }
+
+    /// Verify DPoP proofs with `config` instead of the defaults (60 second
+    /// proof lifetime, any asymmetric algorithm).
+    ///
+    /// Clones of the manager share the `jti` replay cache.
+    pub fn with_dpop(mut self, config: DpopConfig) -> Self {
+        self.dpop = Arc::new(DpopVerifier::new(config));
+        self
+    }
+
+    /// The verifier checking DPoP proofs.
+    pub fn dpop(&self) -> &DpopVerifier {
+        &self.dpop
+    }
+
+    /// The issuer registry used to route tokens.
+    pub fn issuers(&self) -> &IssuerRegistry {
+        &self.issuers
+    }
+
+    /// The config a token is verified with: the one its issuer routes to, or
+    /// the manager's own config. A token is never sent to more than one
+    /// introspection endpoint.
+    fn config_for(&self, token: &str) -> Result<&AuthConfig, USSOError> {
+        if self.issuers.is_empty() {
+            self.jwt_configs.first().ok_or(USSOError::Unauthorized)
+        } else {
+            self.issuers.route(token)
+        }
+    }
+
+    /// The OpenID provider metadata this manager was configured from, if any.
+    pub fn provider_metadata(&self) -> Option<&ProviderMetadata> {
+        self.provider_metadata.as_deref()
+    }
+
+    /// The cache holding the key sets of the configured JWKS URLs.
+    pub fn jwks_cache(&self) -> &Arc<JwksCache> {
+        &self.jwks_cache
+    }
+
+    /// Fetch (blocking) the key sets of all configured JWKS URLs ahead of the
+    /// first request.
+    pub fn prefetch_jwks(&self) -> Result<(), JwksError> {
+        let configs = self.jwt_configs.iter().chain(self.issuers.configs());
+        for jwks_url in configs.filter_map(|c| c.jwks_url.as_deref()) {
+            self.jwks_cache.get_or_fetch_sync(jwks_url)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: DeserializeOwned> UssoAuth<C> {
     /// Decode and validate a JWT, optionally enforcing the `token_type` claim.
     ///
     /// If `expected_token_type` is `Some("access")`, tokens whose `token_type`
     /// claim is not `"access"` are rejected.
+    ///
+    /// Keys come from the manager's [`JwksCache`]; the network is only used
+    /// (blocking) when a key set is missing, expired, or lacks the token's `kid`.
+    /// Encrypted (JWE) tokens are first decrypted with the keys registered via
+    /// [`with_decryption_keys`](Self::with_decryption_keys). Configs with an
+    /// introspection [`ValidationMode`] send the token to their introspection
+    /// endpoint (blocking) instead of, or after, local verification.
+    ///
+    /// DPoP-bound tokens (with a `cnf.jkt` claim) are rejected here; verify
+    /// them with [`user_data_from_dpop_token`](Self::user_data_from_dpop_token).
     pub fn user_data_from_token(
         &self,
         token: &str,
         expected_token_type: Option<&str>,
-    ) -> Result<UserData, USSOError> {
-        for config in &self.jwt_configs {
-            if let Some(jwks_url) = &config.jwks_url {
-                if let Ok(jwks) = fetch_jwks_sync(jwks_url) {
-                    let jwt_config = JWTConfig::new(Some(jwks_url.clone()), Some(jwks));
-                    if let Ok(user_data) = jwt_config.decode(token) {
-                        let token_type = user_data.token_type.as_deref().unwrap_or("access");
-                        if let Some(expected) = expected_token_type {
-                            if token_type != expected {
-                                continue;
-                            }
-                        }
-                        return Ok(user_data);
-                    }
+    ) -> Result<C, USSOError> {
+        let claims = self.claims_from_token_sync(token, expected_token_type)?;
+        reject_dpop_bound(&claims)?;
+        deserialize_claims(claims)
+    }
+
+    /// Async variant of [`user_data_from_token`](Self::user_data_from_token).
+    ///
+    /// Missing or stale key sets are fetched with the async HTTP client, so
+    /// this is safe to call from within a tokio runtime.
+    pub async fn user_data_from_token_async(
+        &self,
+        token: &str,
+        expected_token_type: Option<&str>,
+    ) -> Result<C, USSOError> {
+        let claims = self.claims_from_token_async(token, expected_token_type).await?;
+        reject_dpop_bound(&claims)?;
+        deserialize_claims(claims)
+    }
+
+    /// Decode and validate a JWT like [`user_data_from_token`](Self::user_data_from_token),
+    /// then check it against a step-up `requirement` (blocking).
+    ///
+    /// Valid tokens that authenticated too weakly or too long ago fail with
+    /// `USSOError::StepUpRequired`, which carries the RFC 9470 challenge.
+    pub fn user_data_from_token_with_step_up(
+        &self,
+        token: &str,
+        expected_token_type: Option<&str>,
+        requirement: &StepUpRequirement,
+    ) -> Result<C, USSOError> {
+        let claims = self.claims_from_token_sync(token, expected_token_type)?;
+        reject_dpop_bound(&claims)?;
+        requirement.check_claims(&claims)?;
+        deserialize_claims(claims)
+    }
+
+    /// Async variant of [`user_data_from_token_with_step_up`](Self::user_data_from_token_with_step_up).
+    pub async fn user_data_from_token_with_step_up_async(
+        &self,
+        token: &str,
+        expected_token_type: Option<&str>,
+        requirement: &StepUpRequirement,
+    ) -> Result<C, USSOError> {
+        let claims = self.claims_from_token_async(token, expected_token_type).await?;
+        reject_dpop_bound(&claims)?;
+        requirement.check_claims(&claims)?;
+        deserialize_claims(claims)
+    }
+
+    /// Verify a DPoP-bound access token presented as `Authorization: DPoP
+    /// <token>` together with its `DPoP` `proof` (blocking).
+    ///
+    /// The token is verified like in [`user_data_from_token`](Self::user_data_from_token),
+    /// the proof by the manager's [`DpopVerifier`] for the request `method`
+    /// and `url`, and the proof key must match the token's `cnf.jkt` claim.
+    pub fn user_data_from_dpop_token(
+        &self,
+        token: &str,
+        proof: &str,
+        method: &str,
+        url: &str,
+        expected_token_type: Option<&str>,
+    ) -> Result<C, USSOError> {
+        let claims = self.claims_from_token_sync(token, expected_token_type)?;
+        self.check_dpop_binding(&claims, token, proof, method, url)?;
+        deserialize_claims(claims)
+    }
+
+    /// Async variant of [`user_data_from_dpop_token`](Self::user_data_from_dpop_token).
+    pub async fn user_data_from_dpop_token_async(
+        &self,
+        token: &str,
+        proof: &str,
+        method: &str,
+        url: &str,
+        expected_token_type: Option<&str>,
+    ) -> Result<C, USSOError> {
+        let claims = self.claims_from_token_async(token, expected_token_type).await?;
+        self.check_dpop_binding(&claims, token, proof, method, url)?;
+        deserialize_claims(claims)
+    }
+
+    /// Verify `proof` and check that its key is the one `claims` are bound to.
+    fn check_dpop_binding(
+        &self,
+        claims: &serde_json::Value,
+        token: &str,
+        proof: &str,
+        method: &str,
+        url: &str,
+    ) -> Result<(), USSOError> {
+        let proof = self.dpop.verify(proof, method, url, Some(token))?;
+        match bound_key_thumbprint(claims) {
+            Some(jkt) if jkt == proof.jkt => Ok(()),
+            Some(_) => Err(USSOError::InvalidDpopProof(
+                "proof key does not match cnf.jkt".into(),
+            )),
+            None => Err(USSOError::InvalidDpopProof("token is not DPoP-bound".into())),
+        }
+    }
+
+    /// Verify `token` with the config it routes to and return its claims.
+    fn claims_from_token_sync(
+        &self,
+        token: &str,
+        expected_token_type: Option<&str>,
+    ) -> Result<serde_json::Value, USSOError> {
+        let jws = self.inner_jws(token)?;
+        let config = self.config_for(&jws)?;
+        let claims = match config.validation_mode {
+            ValidationMode::LocalJwt => self.verify_local_sync(&jws, config),
+            ValidationMode::Introspect => introspect_sync(token, config),
+            ValidationMode::LocalThenIntrospect => match self.verify_local_sync(&jws, config) {
+                Err(err) if needs_introspection(&err) => introspect_sync(token, config),
+                result => result,
+            },
+        };
+        self.accept(claims?, expected_token_type)
+    }
+
+    /// Async variant of [`claims_from_token_sync`](Self::claims_from_token_sync).
+    async fn claims_from_token_async(
+        &self,
+        token: &str,
+        expected_token_type: Option<&str>,
+    ) -> Result<serde_json::Value, USSOError> {
+        let jws = self.inner_jws(token)?;
+        let config = self.config_for(&jws)?;
+        let claims = match config.validation_mode {
+            ValidationMode::LocalJwt => self.verify_local_async(&jws, config).await,
+            ValidationMode::Introspect => introspect_async(token, config).await,
+            ValidationMode::LocalThenIntrospect => {
+                match self.verify_local_async(&jws, config).await {
+                    Err(err) if needs_introspection(&err) => introspect_async(token, config).await,
+                    result => result,
                 }
             }
+        };
+        self.accept(claims?, expected_token_type)
+    }
+
+    /// Verify `token` with the keys of `config`, fetching its JWKS (blocking)
+    /// when needed.
+    fn verify_local_sync(
+        &self,
+        token: &str,
+        config: &AuthConfig,
+    ) -> Result<serde_json::Value, USSOError> {
+        let header = JwtHeader::from_token(token)?;
+        let key = match (local_key(config, &header), &config.jwks_url, header.kid.as_deref()) {
+            (Some(key), _, _) => key?,
+            (None, _, None) => return Err(USSOError::MalformedToken("header has no kid".into())),
+            (None, Some(jwks_url), Some(kid)) => self.jwks_cache.find_key_sync(jwks_url, kid)?,
+            (None, None, Some(kid)) => return Err(USSOError::UnknownKey(kid.to_string())),
+        };
+        verify_with_key(&key, token, config)
+    }
+
+    /// Async variant of [`verify_local_sync`](Self::verify_local_sync).
+    async fn verify_local_async(
+        &self,
+        token: &str,
+        config: &AuthConfig,
+    ) -> Result<serde_json::Value, USSOError> {
+        let header = JwtHeader::from_token(token)?;
+        let key = match (local_key(config, &header), &config.jwks_url, header.kid.as_deref()) {
+            (Some(key), _, _) => key?,
+            (None, _, None) => return Err(USSOError::MalformedToken("header has no kid".into())),
+            (None, Some(jwks_url), Some(kid)) => {
+                self.jwks_cache.find_key_async(jwks_url, kid).await?
+            }
+            (None, None, Some(kid)) => return Err(USSOError::UnknownKey(kid.to_string())),
+        };
+        verify_with_key(&key, token, config)
+    }
+
+    /// Checks shared by every validation mode: token type and revocation.
+    fn accept(
+        &self,
+        claims: serde_json::Value,
+        expected_token_type: Option<&str>,
+    ) -> Result<serde_json::Value, USSOError> {
+        if !token_type_matches(&claims, expected_token_type) {
+            return Err(USSOError::InvalidTokenType);
         }
-        Err(USSOError::Unauthorized)
+        if let Some(checker) = &self.revocation {
+            let claim = |name| claims.get(name).and_then(|value| value.as_str());
+            if checker.is_revoked(claim("jti"), claim("session_id")) {
+                return Err(USSOError::RevokedToken);
+            }
+        }
+        Ok(claims)
     }
 
     /// Verify an API key against the USSO backend and return the associated user data.
     ///
-    /// Sends a POST request to the API key verify endpoint (`/api/sso/v1/apikeys/verify`).
+    /// Sends a POST request to the [verify endpoint](APIHeaderConfig::verify_endpoint)
+    /// of each config, by default `/api/sso/v1/apikeys/verify` on the base URL.
     pub fn user_data_from_api_key(
         &self,
         api_key: &str,
         _headers: &HashMap<String, String>,
-    ) -> Result<UserData, USSOError> {
+    ) -> Result<C, USSOError> {
         for config in &self.jwt_configs {
             if let Some(api_header) = &config.api_key_header {
-                let url = if api_header.verify_endpoint.is_empty() {
-                    format!(
-                        "{}/api/sso/v1/apikeys/verify",
-                        self.from_usso_base_url
-                            .as_deref()
-                            .unwrap_or("https://sso.usso.io")
-                    )
-                } else {
-                    api_header.verify_endpoint.clone()
-                };
+                let url = self.api_key_verify_url(api_header);
                 let client = reqwest::blocking::Client::new();
                 let response = client
                     .post(&url)
@@ -379,7 +1101,7 @@ impl UssoAuth {
                     .send()
                     .map_err(|_| USSOError::Unauthorized)?;
                 if response.status().is_success() {
-                    let data: UserData = response
+                    let data: C = response
                         .json()
                         .map_err(|_| USSOError::Unauthorized)?;
                     return Ok(data);
@@ -389,20 +1111,44 @@ impl UssoAuth {
         Err(USSOError::Unauthorized)
     }
 
-    /// Heuristically detect the type of a compact token.
-    ///
-    /// - 3 dot-separated parts → `"jwt"`
-    /// - 5 dot-separated parts → `"jwe"`
-    /// - anything else → `None`
-    pub fn detect_compact_token_type(token: &str) -> Option<&'static str> {
-        let token = token.trim();
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() == 3 {
-            Some("jwt")
-        } else if parts.len() == 5 {
-            Some("jwe")
+    /// Async variant of [`user_data_from_api_key`](Self::user_data_from_api_key).
+    pub async fn user_data_from_api_key_async(
+        &self,
+        api_key: &str,
+        _headers: &HashMap<String, String>,
+    ) -> Result<C, USSOError> {
+        for config in &self.jwt_configs {
+            if let Some(api_header) = &config.api_key_header {
+                let url = self.api_key_verify_url(api_header);
+                let response = self
+                    .http_client
+                    .post(&url)
+                    .json(&serde_json::json!({"api_key": api_key}))
+                    .send()
+                    .await
+                    .map_err(|_| USSOError::Unauthorized)?;
+                if response.status().is_success() {
+                    let data: C = response
+                        .json()
+                        .await
+                        .map_err(|_| USSOError::Unauthorized)?;
+                    return Ok(data);
+                }
+            }
+        }
+        Err(USSOError::Unauthorized)
+    }
+
+    fn api_key_verify_url(&self, api_header: &APIHeaderConfig) -> String {
+        if api_header.verify_endpoint.is_empty() {
+            format!(
+                "{}/api/sso/v1/apikeys/verify",
+                self.from_usso_base_url
+                    .as_deref()
+                    .unwrap_or("https://sso.usso.io")
+            )
         } else {
-            None
+            api_header.verify_endpoint.clone()
         }
     }
 }
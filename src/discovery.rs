@@ -0,0 +1,130 @@
+//! OpenID Connect discovery.
+//!
+//! Reads `{base}/.well-known/openid-configuration` (OpenID Connect Discovery
+//! 1.0) so services can be configured from the identity server itself
+//! instead of hard-coding the JWKS URL, issuer and endpoints.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::core::UssoAuth;
+//! use usso::discovery::ProviderMetadata;
+//!
+//! // Just the metadata:
+//! let metadata = ProviderMetadata::fetch_sync("https://sso.usso.io").unwrap();
+//! println!("keys at {}", metadata.jwks_uri);
+//!
+//! // Or a fully configured manager:
+//! let auth = UssoAuth::from_discovery_sync("https://sso.usso.io").unwrap();
+//! ```
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{APIHeaderConfig, AuthConfig, TokenValidation};
+use crate::exceptions::DiscoveryError;
+use crate::introspection::{IntrospectionClient, IntrospectionConfig};
+use crate::jwks::default_headers;
+
+/// The subset of OpenID provider metadata used by this crate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub jwks_uri: String,
+    pub token_endpoint: Option<String>,
+    pub introspection_endpoint: Option<String>,
+    pub revocation_endpoint: Option<String>,
+    pub userinfo_endpoint: Option<String>,
+    /// USSO extension: where API keys are verified, instead of
+    /// `/api/sso/v1/apikeys/verify` on the base URL.
+    pub api_key_verify_endpoint: Option<String>,
+    /// Algorithms for ID tokens only; access tokens are not restricted by it.
+    #[serde(default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+}
+
+impl ProviderMetadata {
+    /// The discovery document URL for `base_url`.
+    pub fn discovery_url(base_url: &str) -> String {
+        format!(
+            "{}/.well-known/openid-configuration",
+            base_url.trim_end_matches('/')
+        )
+    }
+
+    /// Fetch (blocking) and check the discovery document of `base_url`.
+    pub fn fetch_sync(base_url: &str) -> Result<Self, DiscoveryError> {
+        let client = reqwest::blocking::Client::builder()
+            .default_headers(default_headers())
+            .build()?;
+        let metadata: ProviderMetadata = client
+            .get(Self::discovery_url(base_url))
+            .send()?
+            .error_for_status()?
+            .json()?;
+        metadata.check_issuer(base_url)
+    }
+
+    /// Fetch (async) and check the discovery document of `base_url`.
+    pub async fn fetch_async(base_url: &str) -> Result<Self, DiscoveryError> {
+        let client = reqwest::Client::builder()
+            .default_headers(default_headers())
+            .build()?;
+        let metadata: ProviderMetadata = client
+            .get(Self::discovery_url(base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        metadata.check_issuer(base_url)
+    }
+
+    /// The issuer must be the URL the document was fetched from (OpenID
+    /// Connect Discovery 1.0, section 4.3), otherwise anyone able to serve
+    /// the document could impersonate the identity server.
+    fn check_issuer(self, base_url: &str) -> Result<Self, DiscoveryError> {
+        if self.issuer.trim_end_matches('/') != base_url.trim_end_matches('/') {
+            return Err(DiscoveryError::IssuerMismatch {
+                expected: base_url.to_string(),
+                found: self.issuer,
+            });
+        }
+        if self.jwks_uri.is_empty() {
+            return Err(DiscoveryError::InvalidMetadata("empty jwks_uri".into()));
+        }
+        Ok(self)
+    }
+
+    /// An [`AuthConfig`] using the advertised `jwks_uri` and expecting the
+    /// advertised `issuer`.
+    ///
+    /// An advertised `introspection_endpoint` becomes its
+    /// [`introspection`](AuthConfig::introspection) client (without client
+    /// credentials; tokens are still verified locally until the
+    /// [`validation_mode`](AuthConfig::validation_mode) is changed), and an
+    /// advertised `api_key_verify_endpoint` its API key verify endpoint.
+    pub fn auth_config(&self) -> AuthConfig {
+        let introspection = self.introspection_endpoint.as_ref().map(|endpoint| {
+            Arc::new(IntrospectionClient::new(IntrospectionConfig::new(endpoint)))
+        });
+        let api_key_header = match &self.api_key_verify_endpoint {
+            Some(endpoint) => APIHeaderConfig {
+                verify_endpoint: endpoint.clone(),
+                ..Default::default()
+            },
+            None => APIHeaderConfig::default(),
+        };
+        AuthConfig {
+            jwks_url: Some(self.jwks_uri.clone()),
+            api_key_header: Some(api_key_header),
+            validation: TokenValidation {
+                issuers: Some(vec![self.issuer.clone()]),
+                ..Default::default()
+            },
+            introspection,
+            ..Default::default()
+        }
+    }
+}
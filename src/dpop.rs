@@ -0,0 +1,267 @@
+//! DPoP (RFC 9449) proof verification for sender-constrained tokens.
+//!
+//! A DPoP-bound access token carries the RFC 7638 thumbprint of the client's
+//! public key in its `cnf.jkt` claim, and every request comes with a fresh
+//! proof JWT in the `DPoP` header, signed with that key. A [`DpopVerifier`]
+//! checks the proof:
+//!
+//! - `typ` is `dpop+jwt`, `alg` is an asymmetric algorithm, and the proof
+//!   verifies with the public `jwk` embedded in its header;
+//! - `htm` and `htu` match the request method and URL (ignoring query and
+//!   fragment);
+//! - `iat` is within [`max_age`](DpopConfig::max_age) (plus
+//!   [`leeway`](DpopConfig::leeway) for clock skew);
+//! - the `jti` has not been seen before;
+//! - `ath` is the SHA-256 hash of the access token it accompanies.
+//!
+//! [`UssoAuth::user_data_from_dpop_token`](crate::core::UssoAuth::user_data_from_dpop_token)
+//! combines this with token verification and the `cnf.jkt` check. Failures
+//! are reported as [`USSOError::InvalidDpopProof`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::core::UssoAuth;
+//! use usso::dpop::DpopConfig;
+//!
+//! let auth = UssoAuth::new(None, Some("https://sso.usso.io".into()))
+//!     .with_dpop(DpopConfig::default());
+//!
+//! // From `Authorization: DPoP <token>` and the `DPoP` header:
+//! let user = auth.user_data_from_dpop_token(
+//!     "eyJ...",
+//!     "eyJ...",
+//!     "POST",
+//!     "https://api.example.com/orders",
+//!     Some("access"),
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::Url;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::core::{check_key_for_algorithm, verify_token};
+use crate::exceptions::USSOError;
+use crate::schemas::Jwk;
+
+/// JWK members that only appear in private or symmetric keys.
+const PRIVATE_KEY_MEMBERS: [&str; 7] = ["d", "p", "q", "dp", "dq", "qi", "k"];
+
+/// Rules for accepting DPoP proofs.
+#[derive(Debug, Clone)]
+pub struct DpopConfig {
+    /// How long after its `iat` a proof is accepted; also how long its `jti`
+    /// is remembered.
+    pub max_age: Duration,
+    /// Allowed clock skew for proofs whose `iat` is in the future.
+    pub leeway: Duration,
+    /// Accepted proof `alg` values; `None` accepts every supported asymmetric
+    /// algorithm.
+    pub algorithms: Option<Vec<String>>,
+    /// Scheme and host the service is reached at (e.g.
+    /// `https://api.example.com`), used by framework integrations to rebuild
+    /// the request URL a proof's `htu` is checked against.
+    pub public_base_url: Option<String>,
+    /// Let framework integrations take the scheme and host from the
+    /// `X-Forwarded-Proto`/`X-Forwarded-Host` headers. Only enable this behind
+    /// a proxy that overwrites them; otherwise clients choose the URL.
+    pub trust_forwarded_headers: bool,
+}
+
+impl Default for DpopConfig {
+    fn default() -> Self {
+        DpopConfig {
+            max_age: Duration::from_secs(60),
+            leeway: Duration::from_secs(5),
+            algorithms: None,
+            public_base_url: None,
+            trust_forwarded_headers: false,
+        }
+    }
+}
+
+/// A verified DPoP proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DpopProof {
+    /// RFC 7638 thumbprint of the proof key, to compare with `cnf.jkt`.
+    pub jkt: String,
+    pub jti: String,
+    pub iat: i64,
+}
+
+/// Verifies DPoP proofs and remembers their `jti`s to reject replays.
+#[derive(Debug, Default)]
+pub struct DpopVerifier {
+    config: DpopConfig,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl DpopVerifier {
+    /// Create a verifier with an empty replay cache.
+    pub fn new(config: DpopConfig) -> Self {
+        DpopVerifier {
+            config,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The rules of this verifier.
+    pub fn config(&self) -> &DpopConfig {
+        &self.config
+    }
+
+    /// Verify `proof` for a request with `method` to `url`.
+    ///
+    /// When `access_token` is given, the proof must carry its `ath` hash.
+    /// A proof is accepted only once.
+    pub fn verify(
+        &self,
+        proof: &str,
+        method: &str,
+        url: &str,
+        access_token: Option<&str>,
+    ) -> Result<DpopProof, USSOError> {
+        let (alg, jwk) = proof_header(proof)?;
+        if let Some(allowed) = &self.config.algorithms {
+            if !allowed.contains(&alg) {
+                return Err(invalid(format!("algorithm {alg} is not allowed")));
+            }
+        }
+        let claims = check_key_for_algorithm(&jwk, &alg)
+            .and_then(|()| verify_token(&jwk, proof, &alg))
+            .map_err(|err| invalid(err.to_string()))?;
+
+        let claim = |name: &str| {
+            claims
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid(format!("missing {name}")))
+        };
+        if claim("htm")? != method {
+            return Err(invalid("htm does not match the request method"));
+        }
+        let target = request_uri(url).ok_or_else(|| invalid("invalid request URL"))?;
+        if request_uri(claim("htu")?) != Some(target) {
+            return Err(invalid("htu does not match the request URL"));
+        }
+        if let Some(access_token) = access_token {
+            let ath = URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes()));
+            if claim("ath")? != ath {
+                return Err(invalid("ath does not match the access token"));
+            }
+        }
+
+        let iat = claims
+            .get("iat")
+            .and_then(Value::as_i64)
+            .ok_or_else(|| invalid("missing iat"))?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let max_age = self.config.max_age.as_secs() as i64;
+        let leeway = self.config.leeway.as_secs() as i64;
+        if iat > now + leeway || iat < now - max_age - leeway {
+            return Err(invalid("iat is outside the acceptance window"));
+        }
+
+        let jti = claim("jti")?.to_string();
+        self.remember(&jti)?;
+        Ok(DpopProof {
+            jkt: jwk_thumbprint(&jwk)?,
+            jti,
+            iat,
+        })
+    }
+
+    /// Record `jti`, failing if it was already used within the window.
+    fn remember(&self, jti: &str) -> Result<(), USSOError> {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        seen.retain(|_, expires_at| *expires_at > now);
+        if seen.contains_key(jti) {
+            return Err(invalid("proof has already been used"));
+        }
+        let ttl = self.config.max_age + self.config.leeway * 2;
+        seen.insert(jti.to_string(), now + ttl);
+        Ok(())
+    }
+}
+
+/// The RFC 7638 thumbprint of `jwk`: the base64url SHA-256 of its required
+/// members in lexicographic order.
+pub fn jwk_thumbprint(jwk: &Jwk) -> Result<String, USSOError> {
+    let member = |name: &str, value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|value| format!("\"{name}\":{}", Value::from(value)))
+            .ok_or_else(|| USSOError::InvalidKey(format!("missing {name}")))
+    };
+    let kty = format!("\"kty\":{}", Value::from(jwk.kty.as_str()));
+    let members = match jwk.kty.as_str() {
+        "RSA" => vec![member("e", &jwk.e)?, kty, member("n", &jwk.n)?],
+        "EC" => vec![
+            member("crv", &jwk.crv)?,
+            kty,
+            member("x", &jwk.x)?,
+            member("y", &jwk.y)?,
+        ],
+        "OKP" => vec![member("crv", &jwk.crv)?, kty, member("x", &jwk.x)?],
+        "oct" => vec![member("k", &jwk.k)?, kty],
+        other => return Err(USSOError::InvalidKey(format!("unsupported kty {other}"))),
+    };
+    let canonical = format!("{{{}}}", members.join(","));
+    Ok(URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes())))
+}
+
+fn invalid(reason: impl Into<String>) -> USSOError {
+    USSOError::InvalidDpopProof(reason.into())
+}
+
+/// Check the proof header and return its `alg` and public key.
+fn proof_header(proof: &str) -> Result<(String, Jwk), USSOError> {
+    let segment = proof
+        .split('.')
+        .next()
+        .ok_or_else(|| invalid("not a JWT"))?;
+    let header: Value = URL_SAFE_NO_PAD
+        .decode(segment)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid("malformed header"))?;
+    if header.get("typ").and_then(Value::as_str) != Some("dpop+jwt") {
+        return Err(invalid("typ must be dpop+jwt"));
+    }
+    let alg = header
+        .get("alg")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("missing alg"))?;
+    if alg == "none" || alg.starts_with("HS") {
+        return Err(invalid(format!("{alg} is not an asymmetric algorithm")));
+    }
+    let jwk = header
+        .get("jwk")
+        .and_then(Value::as_object)
+        .ok_or_else(|| invalid("missing jwk"))?;
+    if PRIVATE_KEY_MEMBERS.iter().any(|name| jwk.contains_key(*name)) {
+        return Err(invalid("jwk must be a public key"));
+    }
+    let jwk = serde_json::from_value(Value::Object(jwk.clone()))
+        .map_err(|err| invalid(format!("invalid jwk: {err}")))?;
+    Ok((alg.to_string(), jwk))
+}
+
+/// `url` without its query and fragment, as compared by `htu`.
+fn request_uri(url: &str) -> Option<Url> {
+    let mut url = Url::parse(url).ok()?;
+    url.set_query(None);
+    url.set_fragment(None);
+    Some(url)
+}
@@ -1,7 +1,11 @@
 //! Error types for the USSO client.
 //!
 //! - [`USSOError`] — authentication and authorization errors
+//! - [`ClaimError`] — registered-claim validation failures
+//! - [`StepUpChallenge`] — an RFC 9470 step-up authentication challenge
+//! - [`ScopeParseError`] — invalid [`Scope`](crate::authorization::Scope) strings
 //! - [`JwksError`] — JWKS fetching and caching errors
+//! - [`DiscoveryError`] — OpenID Connect discovery errors
 //! - [`JwtError`] — JWT parsing errors
 
 use thiserror::Error;
@@ -10,12 +14,59 @@ use thiserror::Error;
 ///
 /// Each variant maps to an HTTP status code via [`status_code`](Self::status_code)
 /// and a machine-readable error code via [`error_code`](Self::error_code).
+///
+/// Token verification reports why a token was rejected:
+///
+/// | Variant | Cause |
+/// |---------|-------|
+/// | [`MalformedToken`](Self::MalformedToken) | Not a well-formed JWT (structure, base64, JSON, missing `kid`) |
+/// | [`UnknownIssuer`](Self::UnknownIssuer) | No registered tenant for the token's `iss` or `host` |
+/// | [`UnknownKey`](Self::UnknownKey) | No key with the token's `kid` in the configured key sets |
+/// | [`UnsupportedAlgorithm`](Self::UnsupportedAlgorithm) | The token's `alg` is not implemented |
+/// | [`AlgorithmNotAllowed`](Self::AlgorithmNotAllowed) | The `alg` is not on the allow-list |
+/// | [`KeyMismatch`](Self::KeyMismatch) / [`InvalidKey`](Self::InvalidKey) | The key cannot verify the token |
+/// | [`InvalidCertificate`](Self::InvalidCertificate) | The key's `x5c` chain is malformed or untrusted |
+/// | [`InvalidSignature`](Self::InvalidSignature) | The signature does not match |
+/// | [`ExpiredToken`](Self::ExpiredToken) / [`InvalidClaim`](Self::InvalidClaim) | A claim failed validation |
+/// | [`RevokedToken`](Self::RevokedToken) | A valid token was revoked (by `jti` or session) |
+/// | [`InactiveToken`](Self::InactiveToken) | Introspection reported the token as not active |
+/// | [`IntrospectionUnavailable`](Self::IntrospectionUnavailable) | The introspection endpoint failed (HTTP 503) |
+/// | [`InvalidDpopProof`](Self::InvalidDpopProof) | A DPoP proof is missing, invalid, replayed, or not bound to the token |
+/// | [`StepUpRequired`](Self::StepUpRequired) | A valid token does not meet a [step-up requirement](crate::step_up) |
+/// | [`JwksUnavailable`](Self::JwksUnavailable) | The key set could not be fetched (HTTP 503) |
+/// | [`DecryptionFailed`](Self::DecryptionFailed) | An encrypted (JWE) token could not be decrypted |
 #[derive(Error, Debug)]
 pub enum USSOError {
     #[error("Invalid signature")]
     InvalidSignature,
     #[error("Invalid token")]
     InvalidToken,
+    #[error("Malformed token: {0}")]
+    MalformedToken(String),
+    #[error("No key found for kid: {0}")]
+    UnknownKey(String),
+    #[error("Unsupported algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("JWKS unavailable: {0}")]
+    JwksUnavailable(#[source] JwksError),
+    #[error("Decryption failed: {0}")]
+    DecryptionFailed(String),
+    #[error("Unknown issuer: {0}")]
+    UnknownIssuer(String),
+    #[error("Invalid certificate: {0}")]
+    InvalidCertificate(String),
+    #[error("Token has been revoked")]
+    RevokedToken,
+    #[error("Token is not active")]
+    InactiveToken,
+    #[error("Introspection unavailable: {0}")]
+    IntrospectionUnavailable(String),
+    #[error("Invalid DPoP proof: {0}")]
+    InvalidDpopProof(String),
+    #[error("Step-up authentication required: {0}")]
+    StepUpRequired(StepUpChallenge),
     #[error("Expired token")]
     ExpiredToken,
     #[error("Unauthorized")]
@@ -24,15 +75,24 @@ pub enum USSOError {
     InvalidTokenType,
     #[error("Permission denied")]
     PermissionDenied,
+    #[error("Invalid claim: {0}")]
+    InvalidClaim(#[from] ClaimError),
+    #[error("Algorithm not allowed: {0}")]
+    AlgorithmNotAllowed(String),
+    #[error("Key cannot verify this token: {0}")]
+    KeyMismatch(String),
     #[error("{0}")]
     Other(String),
 }
 
 impl USSOError {
-    /// Returns the HTTP status code for this error (401 for most, 403 for permission denied).
+    /// Returns the HTTP status code for this error (401 for most, 403 for
+    /// permission denied, 503 when the JWKS or the introspection endpoint
+    /// could not be reached).
     pub fn status_code(&self) -> u16 {
         match self {
             USSOError::PermissionDenied => 403,
+            USSOError::JwksUnavailable(_) | USSOError::IntrospectionUnavailable(_) => 503,
             _ => 401,
         }
     }
@@ -42,13 +102,141 @@ impl USSOError {
         match self {
             USSOError::InvalidSignature => "invalid_signature",
             USSOError::InvalidToken => "invalid_token",
+            USSOError::MalformedToken(_) => "malformed_token",
+            USSOError::UnknownKey(_) => "unknown_kid",
+            USSOError::UnsupportedAlgorithm(_) => "unsupported_algorithm",
+            USSOError::InvalidKey(_) => "invalid_key",
+            USSOError::JwksUnavailable(_) => "jwks_unavailable",
+            USSOError::DecryptionFailed(_) => "decryption_failed",
+            USSOError::UnknownIssuer(_) => "unknown_issuer",
+            USSOError::InvalidCertificate(_) => "invalid_certificate",
+            USSOError::RevokedToken => "revoked_token",
+            USSOError::InactiveToken => "inactive_token",
+            USSOError::IntrospectionUnavailable(_) => "introspection_unavailable",
+            USSOError::InvalidDpopProof(_) => "invalid_dpop_proof",
+            USSOError::StepUpRequired(_) => "insufficient_user_authentication",
             USSOError::ExpiredToken => "expired_signature",
             USSOError::Unauthorized => "unauthorized",
             USSOError::InvalidTokenType => "invalid_token_type",
             USSOError::PermissionDenied => "permission_denied",
+            USSOError::InvalidClaim(err) => err.error_code(),
+            USSOError::AlgorithmNotAllowed(_) => "algorithm_not_allowed",
+            USSOError::KeyMismatch(_) => "key_mismatch",
             USSOError::Other(_) => "error",
         }
     }
+
+    /// The claim that failed validation, if this is a claim failure.
+    pub fn claim(&self) -> Option<&str> {
+        match self {
+            USSOError::ExpiredToken => Some("exp"),
+            USSOError::InvalidClaim(err) => Some(err.claim()),
+            _ => None,
+        }
+    }
+}
+
+impl From<JwksError> for USSOError {
+    fn from(err: JwksError) -> Self {
+        match err {
+            JwksError::UnknownKid(kid) => USSOError::UnknownKey(kid),
+            other => USSOError::JwksUnavailable(other),
+        }
+    }
+}
+
+impl From<JwtError> for USSOError {
+    fn from(err: JwtError) -> Self {
+        USSOError::MalformedToken(err.to_string())
+    }
+}
+
+/// A registered claim that failed validation (see [`TokenValidation`](crate::config::TokenValidation)).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClaimError {
+    #[error("token is not valid yet (nbf)")]
+    NotYetValid,
+    #[error("token was issued in the future (iat)")]
+    IssuedInFuture,
+    #[error("unexpected issuer: {0}")]
+    InvalidIssuer(String),
+    #[error("token is not intended for this audience")]
+    InvalidAudience,
+    #[error("missing required claim: {0}")]
+    Missing(String),
+    #[error("malformed claim: {0}")]
+    Malformed(String),
+}
+
+impl ClaimError {
+    /// Returns a machine-readable error code string for this claim failure.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ClaimError::NotYetValid => "immature_token",
+            ClaimError::IssuedInFuture => "invalid_iat",
+            ClaimError::InvalidIssuer(_) => "invalid_issuer",
+            ClaimError::InvalidAudience => "invalid_audience",
+            ClaimError::Missing(_) => "missing_claim",
+            ClaimError::Malformed(_) => "malformed_claim",
+        }
+    }
+
+    /// The name of the claim that failed validation.
+    pub fn claim(&self) -> &str {
+        match self {
+            ClaimError::NotYetValid => "nbf",
+            ClaimError::IssuedInFuture => "iat",
+            ClaimError::InvalidIssuer(_) => "iss",
+            ClaimError::InvalidAudience => "aud",
+            ClaimError::Missing(name) | ClaimError::Malformed(name) => name,
+        }
+    }
+}
+
+/// Why a token must be stepped up, and what the client should ask for.
+///
+/// Rendered as an RFC 9470 challenge by
+/// [`www_authenticate`](Self::www_authenticate).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{reason}")]
+pub struct StepUpChallenge {
+    pub reason: String,
+    /// The `acr` the client should request when re-authenticating.
+    pub acr_values: Option<String>,
+    /// The maximum authentication age, in seconds, the client should request.
+    pub max_age: Option<u64>,
+}
+
+impl StepUpChallenge {
+    /// The `WWW-Authenticate` header value for this challenge, e.g.
+    /// `Bearer error="insufficient_user_authentication", error_description="...", max_age="300"`.
+    pub fn www_authenticate(&self) -> String {
+        let quote = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut challenge = format!(
+            "Bearer error=\"insufficient_user_authentication\", error_description=\"{}\"",
+            quote(&self.reason)
+        );
+        if let Some(acr_values) = &self.acr_values {
+            challenge.push_str(&format!(", acr_values=\"{}\"", quote(acr_values)));
+        }
+        if let Some(max_age) = self.max_age {
+            challenge.push_str(&format!(", max_age=\"{max_age}\""));
+        }
+        challenge
+    }
+}
+
+/// Why a string is not a valid [`Scope`](crate::authorization::Scope).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScopeParseError {
+    #[error("scope has no action: {0}")]
+    MissingAction(String),
+    #[error("unknown action: {0}")]
+    UnknownAction(String),
+    #[error("empty path segment in scope: {0}")]
+    EmptySegment(String),
+    #[error("invalid filter: {0}")]
+    InvalidFilter(String),
 }
 
 /// Errors that can occur when fetching or caching JWKS keys.
@@ -60,13 +248,32 @@ pub enum JwksError {
     InvalidJwksData(String),
     #[error("JWKS not initialized")]
     NotInitialized,
+    #[error("No key found for kid: {0}")]
+    UnknownKid(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Errors that can occur when fetching OpenID Connect provider metadata.
+#[derive(Error, Debug)]
+pub enum DiscoveryError {
+    #[error("HTTP error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("Invalid provider metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("Issuer mismatch: expected {expected}, found {found}")]
+    IssuerMismatch { expected: String, found: String },
 }
 
 /// Errors that can occur when parsing JWT headers or payloads.
-#[derive(Debug)]
+#[derive(Error, Debug)]
 pub enum JwtError {
+    #[error("expected three dot-separated parts")]
     InvalidFormat,
+    #[error("{0}")]
     DecodingError(String),
+    #[error("missing field: {0}")]
     MissingField(String),
+    #[error("{0}")]
     InvalidToken(String),
 }
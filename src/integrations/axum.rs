@@ -13,9 +13,16 @@
 //! |-----------|-------------|
 //! | [`AuthenticatedUser`] | Mandatory auth — rejects with 401 if token is missing or invalid |
 //! | [`OptionalUser`] | Optional auth — returns `None` instead of rejecting |
+//! | [`AuthenticatedClaims`] | Mandatory auth decoding into a custom claims type |
+//! | [`DpopUser`] | Mandatory auth with a DPoP-bound token and its proof |
+//! | [`StepUpUser`] | Mandatory auth meeting the route's [`StepUpRequirement`] |
 //!
-//! Both extractors read a Bearer token from the `Authorization` header and
-//! validate it via `Extension<Arc<UssoAuth>>` which must be added to the router.
+//! The other extractors read a Bearer token from the `Authorization` header and
+//! validate it via `Extension<Arc<UssoAuth>>` (or `Extension<Arc<UssoAuth<C>>>`
+//! for [`AuthenticatedClaims<C>`]) which must be added to the router. Rejections
+//! use the [`USSOError::status_code`] of the failure (401, or 503 when the JWKS
+//! is unreachable) with its [`USSOError::error_code`] as body; step-up
+//! failures also carry the RFC 9470 `WWW-Authenticate` challenge.
 //!
 //! # Example
 //!
@@ -40,17 +47,22 @@ use std::sync::Arc;
 
 use axum::{
     extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
+    http::{header::WWW_AUTHENTICATE, request::Parts, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
+use serde::de::DeserializeOwned;
 
 use crate::core::UssoAuth;
+use crate::dpop::DpopConfig;
+use crate::exceptions::USSOError;
 use crate::schemas::UserData;
+use crate::step_up::StepUpRequirement;
 
 /// Axum extractor that authenticates a user from the request.
 ///
 /// Extracts the Bearer token from the `Authorization` header and validates it
-/// via [`UssoAuth`]. Requires `Extension<Arc<UssoAuth>>` to be added to the
+/// via [`UssoAuth::user_data_from_token_async`], so key fetches never block
+/// the runtime. Requires `Extension<Arc<UssoAuth>>` to be added to the
 /// router (usually via a [`tower::Layer`] or directly on the [`axum::Router`]).
 ///
 /// # Example
@@ -102,6 +114,118 @@ pub struct AuthenticatedUser(pub UserData);
 /// ```
 pub struct OptionalUser(pub Option<UserData>);
 
+/// Axum extractor that authenticates a request into a custom claims type.
+///
+/// Works like [`AuthenticatedUser`] but reads `Extension<Arc<UssoAuth<C>>>`,
+/// so the claims are deserialized once, from the verified token, into `C`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use axum::{Router, routing::get, Extension};
+/// use serde::Deserialize;
+/// use usso::core::UssoAuth;
+/// use usso::integrations::axum::AuthenticatedClaims;
+/// use usso::schemas::UserData;
+///
+/// #[derive(Deserialize, Debug, Clone)]
+/// struct Extra {
+///     plan: String,
+/// }
+///
+/// let auth = Arc::new(
+///     UssoAuth::new(None, Some("https://sso.usso.io".into())).with_claims::<UserData<Extra>>(),
+/// );
+///
+/// async fn handler(claims: AuthenticatedClaims<UserData<Extra>>) -> String {
+///     format!("Plan: {}", claims.0.extra.plan)
+/// }
+///
+/// let app: Router<()> = Router::new()
+///     .route("/plan", get(handler))
+///     .layer(Extension(auth));
+/// ```
+pub struct AuthenticatedClaims<C>(pub C);
+
+/// Axum extractor that authenticates a DPoP-bound access token (RFC 9449).
+///
+/// Reads the token from `Authorization: DPoP <token>` and the proof from the
+/// `DPoP` header, and verifies both via
+/// [`UssoAuth::user_data_from_dpop_token_async`] with the request method and
+/// URL. The URL is built from the request's own `Host` (with `https` unless
+/// the request URI names a scheme). Behind a proxy, set
+/// [`DpopConfig::public_base_url`], or [`DpopConfig::trust_forwarded_headers`]
+/// to use `X-Forwarded-Proto` and `X-Forwarded-Host`, so it matches the `htu`
+/// the client signed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use axum::{Router, routing::post, Extension};
+/// use usso::core::UssoAuth;
+/// use usso::integrations::axum::DpopUser;
+///
+/// let auth = Arc::new(UssoAuth::new(None, Some("https://sso.usso.io".into())));
+///
+/// async fn handler(user: DpopUser) -> String {
+///     format!("Order placed by {}", user.0.sub.as_deref().unwrap_or("unknown"))
+/// }
+///
+/// let app: Router<()> = Router::new()
+///     .route("/orders", post(handler))
+///     .layer(Extension(auth));
+/// ```
+pub struct DpopUser(pub UserData);
+
+/// Axum extractor that authenticates a user and enforces a step-up requirement.
+///
+/// Works like [`AuthenticatedUser`], and additionally checks the token against
+/// the `Extension<StepUpRequirement>` of the route. Tokens that fall short are
+/// rejected with 401 and an RFC 9470 `WWW-Authenticate` challenge, so the
+/// client knows to re-authenticate (e.g. with an OTP).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use axum::{Router, routing::post, Extension};
+/// use usso::core::UssoAuth;
+/// use usso::integrations::axum::StepUpUser;
+/// use usso::step_up::StepUpRequirement;
+///
+/// let auth = Arc::new(UssoAuth::new(None, Some("https://sso.usso.io".into())));
+/// let recent_otp = StepUpRequirement {
+///     required_amr: vec!["otp".into()],
+///     max_age: Some(Duration::from_secs(300)),
+///     ..Default::default()
+/// };
+///
+/// async fn transfer(user: StepUpUser) -> String {
+///     format!("Transfer approved for {}", user.0.sub.as_deref().unwrap_or("unknown"))
+/// }
+///
+/// let app: Router<()> = Router::new()
+///     .route("/transfers", post(transfer))
+///     .route_layer(Extension(recent_otp))
+///     .layer(Extension(auth));
+/// ```
+pub struct StepUpUser(pub UserData);
+
+/// Turn a verification failure into a response carrying its status and error code.
+fn rejection(err: &USSOError) -> Response {
+    let status = StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::UNAUTHORIZED);
+    let mut response = (status, err.error_code().to_string()).into_response();
+    if let USSOError::StepUpRequired(challenge) = err {
+        if let Ok(value) = HeaderValue::from_str(&challenge.www_authenticate()) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, value);
+        }
+    }
+    response
+}
+
 fn extract_bearer_token(headers: &axum::http::HeaderMap) -> Option<String> {
     let value = headers.get("Authorization")?.to_str().ok()?;
     let token = value.strip_prefix("Bearer ")?.trim();
@@ -124,13 +248,32 @@ where
             .ok_or_else(|| (StatusCode::UNAUTHORIZED, "missing token").into_response())?;
 
         let user = auth
-            .user_data_from_token(&token, Some("access"))
-            .map_err(|_| (StatusCode::UNAUTHORIZED, "invalid token").into_response())?;
+            .user_data_from_token_async(&token, Some("access"))
+            .await
+            .map_err(|err| rejection(&err))?;
 
         Ok(AuthenticatedUser(user))
     }
 }
 
+/// The absolute URL of the request, as the client addressed it.
+///
+/// Forwarded headers are only read when `config` trusts them.
+fn request_url(parts: &Parts, config: &DpopConfig) -> Option<String> {
+    if let Some(base_url) = &config.public_base_url {
+        return Some(format!("{}{}", base_url.trim_end_matches('/'), parts.uri.path()));
+    }
+    let header = |name: &str| parts.headers.get(name).and_then(|value| value.to_str().ok());
+    let forwarded = |name: &str| header(name).filter(|_| config.trust_forwarded_headers);
+    let scheme = forwarded("X-Forwarded-Proto")
+        .or(parts.uri.scheme_str())
+        .unwrap_or("https");
+    let host = forwarded("X-Forwarded-Host")
+        .or_else(|| header("Host"))
+        .or(parts.uri.authority().map(|authority| authority.as_str()))?;
+    Some(format!("{scheme}://{host}{}", parts.uri.path()))
+}
+
 impl<S> FromRequestParts<S> for OptionalUser
 where
     S: Send + Sync,
@@ -148,7 +291,7 @@ where
             None => return Ok(OptionalUser(None)),
         };
 
-        let user = match auth.user_data_from_token(&token, Some("access")) {
+        let user = match auth.user_data_from_token_async(&token, Some("access")).await {
             Ok(u) => u,
             Err(_) => return Ok(OptionalUser(None)),
         };
@@ -156,3 +299,97 @@ where
         Ok(OptionalUser(Some(user)))
     }
 }
+
+impl<S, C> FromRequestParts<S> for AuthenticatedClaims<C>
+where
+    S: Send + Sync,
+    C: DeserializeOwned + Send + Sync + 'static,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = parts
+            .extensions
+            .get::<Arc<UssoAuth<C>>>()
+            .cloned()
+            .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "USSO auth not configured").into_response())?;
+
+        let token = extract_bearer_token(&parts.headers)
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "missing token").into_response())?;
+
+        let claims = auth
+            .user_data_from_token_async(&token, Some("access"))
+            .await
+            .map_err(|err| rejection(&err))?;
+
+        Ok(AuthenticatedClaims(claims))
+    }
+}
+
+impl<S> FromRequestParts<S> for DpopUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = parts
+            .extensions
+            .get::<Arc<UssoAuth>>()
+            .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "USSO auth not configured").into_response())?;
+
+        let token = parts
+            .headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("DPoP "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "missing token").into_response())?;
+
+        let mut proofs = parts.headers.get_all("DPoP").iter();
+        let proof = match (proofs.next(), proofs.next()) {
+            (Some(proof), None) => proof.to_str().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| rejection(&USSOError::InvalidDpopProof("expected one DPoP header".into())))?;
+
+        let url = request_url(parts, auth.dpop().config())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "missing host").into_response())?;
+
+        let user = auth
+            .user_data_from_dpop_token_async(token, proof, parts.method.as_str(), &url, Some("access"))
+            .await
+            .map_err(|err| rejection(&err))?;
+
+        Ok(DpopUser(user))
+    }
+}
+
+impl<S> FromRequestParts<S> for StepUpUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = parts
+            .extensions
+            .get::<Arc<UssoAuth>>()
+            .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "USSO auth not configured").into_response())?;
+        let requirement = parts
+            .extensions
+            .get::<StepUpRequirement>()
+            .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "step-up requirement not configured").into_response())?;
+
+        let token = extract_bearer_token(&parts.headers)
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "missing token").into_response())?;
+
+        let user = auth
+            .user_data_from_token_with_step_up_async(&token, Some("access"), requirement)
+            .await
+            .map_err(|err| rejection(&err))?;
+
+        Ok(StepUpUser(user))
+    }
+}
@@ -6,7 +6,7 @@
 //!
 //! | Feature | Module | Description |
 //! |---------|--------|-------------|
-//! | `axum` | [`axum`] | `FromRequestParts` extractors (`AuthenticatedUser`, `OptionalUser`) |
+//! | `axum` | [`axum`] | `FromRequestParts` extractors (`AuthenticatedUser`, `OptionalUser`, `DpopUser`, `StepUpUser`) |
 
 #[cfg(feature = "axum")]
 pub mod axum;
@@ -0,0 +1,249 @@
+//! OAuth 2.0 token introspection (RFC 7662).
+//!
+//! Instead of (or in addition to) verifying a JWT locally, a service can ask
+//! USSO whether a token is active. This also works for opaque tokens, and
+//! reflects logouts and revocations immediately. An [`IntrospectionClient`]
+//! posts tokens to the introspection endpoint and maps active responses into
+//! [`UserData`]; positive results are cached for at most
+//! [`cache_ttl`](IntrospectionConfig::cache_ttl), and never past the token's
+//! `exp`.
+//!
+//! Select it per [`AuthConfig`](crate::config::AuthConfig) through
+//! [`ValidationMode`](crate::config::ValidationMode).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use usso::config::{AuthConfig, ValidationMode};
+//! use usso::core::UssoAuth;
+//! use usso::introspection::{IntrospectionClient, IntrospectionConfig};
+//!
+//! let mut introspection = IntrospectionConfig::new("https://sso.usso.io/oauth/introspect");
+//! introspection.client_id = Some("billing-service".into());
+//! introspection.client_secret = Some("secret".into());
+//!
+//! let mut config = AuthConfig::new(Some("https://sso.usso.io/.well-known/jwks.json".into()));
+//! config.validation_mode = ValidationMode::LocalThenIntrospect;
+//! config.introspection = Some(Arc::new(IntrospectionClient::new(introspection)));
+//!
+//! let auth = UssoAuth::new(Some(config), None);
+//! let user = auth.user_data_from_token("opaque-or-jwt", Some("access"));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::exceptions::USSOError;
+use crate::jwks::default_headers;
+use crate::schemas::UserData;
+
+/// Where and how to introspect tokens.
+#[derive(Debug, Clone)]
+pub struct IntrospectionConfig {
+    /// The introspection endpoint, e.g. the `introspection_endpoint` of the
+    /// [provider metadata](crate::discovery::ProviderMetadata).
+    pub endpoint: String,
+    /// Client credentials sent with HTTP Basic authentication, if set.
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Upper bound for caching an active result.
+    pub cache_ttl: Duration,
+}
+
+impl IntrospectionConfig {
+    /// Introspect at `endpoint` without client credentials, caching active
+    /// results for up to 60 seconds.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        IntrospectionConfig {
+            endpoint: endpoint.into(),
+            client_id: None,
+            client_secret: None,
+            cache_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedClaims {
+    claims: Value,
+    expires_at: Instant,
+}
+
+/// An RFC 7662 client with a cache of active tokens.
+///
+/// Tokens are only kept in the cache as SHA-256 digests.
+#[derive(Debug)]
+pub struct IntrospectionClient {
+    config: IntrospectionConfig,
+    http_client: reqwest::Client,
+    /// Built on first blocking use, so async-only callers never start its
+    /// runtime thread.
+    blocking_client: OnceLock<reqwest::blocking::Client>,
+    cache: Mutex<HashMap<[u8; 32], CachedClaims>>,
+}
+
+impl IntrospectionClient {
+    /// Create a client with an empty cache.
+    pub fn new(config: IntrospectionConfig) -> Self {
+        IntrospectionClient {
+            config,
+            http_client: reqwest::Client::new(),
+            blocking_client: OnceLock::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The configuration of this client.
+    pub fn config(&self) -> &IntrospectionConfig {
+        &self.config
+    }
+
+    /// Introspect `token` (blocking) and return its claims as [`UserData`].
+    ///
+    /// Inactive tokens fail with `USSOError::InactiveToken`; an unreachable or
+    /// failing endpoint with `USSOError::IntrospectionUnavailable`.
+    pub fn introspect_sync(&self, token: &str) -> Result<UserData, USSOError> {
+        into_user_data(self.introspect_claims_sync(token)?)
+    }
+
+    /// Async variant of [`introspect_sync`](Self::introspect_sync).
+    pub async fn introspect_async(&self, token: &str) -> Result<UserData, USSOError> {
+        into_user_data(self.introspect_claims_async(token).await?)
+    }
+
+    /// Introspect `token` (blocking) and return the claims of the response.
+    ///
+    /// The `active` member is removed, and a space-separated `scope` is also
+    /// exposed as a `scopes` array.
+    pub fn introspect_claims_sync(&self, token: &str) -> Result<Value, USSOError> {
+        let key = cache_key(token);
+        if let Some(claims) = self.cached(&key) {
+            return Ok(claims);
+        }
+        let mut request = self
+            .blocking_client
+            .get_or_init(reqwest::blocking::Client::new)
+            .post(&self.config.endpoint)
+            .headers(default_headers())
+            .form(&form(token));
+        if let Some(client_id) = &self.config.client_id {
+            request = request.basic_auth(client_id, self.config.client_secret.as_deref());
+        }
+        let response = request.send().map_err(unavailable)?;
+        if !response.status().is_success() {
+            return Err(unexpected_status(response.status()));
+        }
+        let body = response.json().map_err(unavailable)?;
+        self.accept(key, body)
+    }
+
+    /// Async variant of [`introspect_claims_sync`](Self::introspect_claims_sync).
+    pub async fn introspect_claims_async(&self, token: &str) -> Result<Value, USSOError> {
+        let key = cache_key(token);
+        if let Some(claims) = self.cached(&key) {
+            return Ok(claims);
+        }
+        let mut request = self
+            .http_client
+            .post(&self.config.endpoint)
+            .headers(default_headers())
+            .form(&form(token));
+        if let Some(client_id) = &self.config.client_id {
+            request = request.basic_auth(client_id, self.config.client_secret.as_deref());
+        }
+        let response = request.send().await.map_err(unavailable)?;
+        if !response.status().is_success() {
+            return Err(unexpected_status(response.status()));
+        }
+        let body = response.json().await.map_err(unavailable)?;
+        self.accept(key, body)
+    }
+
+    /// Forget every cached result.
+    pub fn clear_cache(&self) {
+        self.lock_cache().clear();
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, HashMap<[u8; 32], CachedClaims>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn cached(&self, key: &[u8; 32]) -> Option<Value> {
+        let cache = self.lock_cache();
+        cache
+            .get(key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.claims.clone())
+    }
+
+    /// Map an introspection response, caching it when active.
+    fn accept(&self, key: [u8; 32], body: Value) -> Result<Value, USSOError> {
+        let Value::Object(mut claims) = body else {
+            return Err(USSOError::IntrospectionUnavailable(
+                "response is not a JSON object".into(),
+            ));
+        };
+        if claims.remove("active") != Some(Value::Bool(true)) {
+            return Err(USSOError::InactiveToken);
+        }
+        if let Some(Value::String(scope)) = claims.get("scope") {
+            let scopes: Vec<Value> = scope.split_whitespace().map(Value::from).collect();
+            claims.entry("scopes").or_insert(Value::Array(scopes));
+        }
+        let claims = Value::Object(claims);
+
+        if let Some(ttl) = self.cache_ttl(&claims) {
+            let now = Instant::now();
+            let mut cache = self.lock_cache();
+            cache.retain(|_, entry| entry.expires_at > now);
+            cache.insert(
+                key,
+                CachedClaims {
+                    claims: claims.clone(),
+                    expires_at: now + ttl,
+                },
+            );
+        }
+        Ok(claims)
+    }
+
+    /// How long an active result may be cached: `cache_ttl`, cut short by `exp`.
+    fn cache_ttl(&self, claims: &Value) -> Option<Duration> {
+        let Some(exp) = claims.get("exp").and_then(Value::as_i64) else {
+            return Some(self.config.cache_ttl).filter(|ttl| !ttl.is_zero());
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let remaining = Duration::from_secs(u64::try_from(exp - now).ok()?);
+        Some(remaining.min(self.config.cache_ttl)).filter(|ttl| !ttl.is_zero())
+    }
+}
+
+fn form(token: &str) -> [(&str, &str); 2] {
+    [("token", token), ("token_type_hint", "access_token")]
+}
+
+fn cache_key(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+fn unavailable(err: reqwest::Error) -> USSOError {
+    USSOError::IntrospectionUnavailable(err.to_string())
+}
+
+fn unexpected_status(status: reqwest::StatusCode) -> USSOError {
+    USSOError::IntrospectionUnavailable(format!("unexpected status {status}"))
+}
+
+fn into_user_data(claims: Value) -> Result<UserData, USSOError> {
+    serde_json::from_value(claims).map_err(|err| {
+        USSOError::IntrospectionUnavailable(format!("unexpected introspection response: {err}"))
+    })
+}
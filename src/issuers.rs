@@ -0,0 +1,169 @@
+//! Multi-tenant issuer routing.
+//!
+//! An [`IssuerRegistry`] maps each trusted issuer (one per USSO tenant) to its
+//! own [`AuthConfig`] — JWKS URL, allowed audiences, algorithms and claim
+//! rules. [`UssoAuth`](crate::core::UssoAuth) uses it to pick the config
+//! matching a token's `iss` claim (or the `host` field of its header) instead
+//! of trying every config in turn. The routing reads the claims before the
+//! signature is checked, so the selected config always re-checks `iss`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::config::AuthConfig;
+//! use usso::core::UssoAuth;
+//! use usso::issuers::IssuerRegistry;
+//!
+//! let mut billing = AuthConfig::new(Some("https://acme.usso.io/.well-known/jwks.json".into()));
+//! billing.validation.audiences = Some(vec!["billing".into()]);
+//!
+//! let registry = IssuerRegistry::new()
+//!     .with_issuer("https://acme.usso.io", billing)
+//!     .with_issuer(
+//!         "https://globex.usso.io",
+//!         AuthConfig::new(Some("https://globex.usso.io/.well-known/jwks.json".into())),
+//!     );
+//!
+//! let auth = UssoAuth::new(None, None).with_issuers(registry);
+//! let user = auth.user_data_from_token("eyJ...", Some("access"));
+//! ```
+
+use std::collections::HashMap;
+
+use base64::Engine;
+
+use crate::config::AuthConfig;
+use crate::exceptions::USSOError;
+use crate::schemas::JwtHeader;
+
+/// Trusted issuers and the [`AuthConfig`] used for each.
+#[derive(Debug, Clone, Default)]
+pub struct IssuerRegistry {
+    configs: Vec<AuthConfig>,
+    by_issuer: HashMap<String, usize>,
+    by_host: HashMap<String, usize>,
+}
+
+impl IssuerRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust tokens whose `iss` is `issuer`, verifying them with `config`.
+    ///
+    /// Unless `config` already lists accepted issuers, it is restricted to
+    /// `issuer`. Like that check, routing compares `iss` with `issuer`
+    /// exactly, trailing `/` included. Registering the same issuer again
+    /// replaces its config.
+    pub fn register(&mut self, issuer: impl Into<String>, mut config: AuthConfig) -> &mut Self {
+        let issuer = issuer.into();
+        config
+            .validation
+            .issuers
+            .get_or_insert_with(|| vec![issuer.clone()]);
+        let index = self.store(self.by_issuer.get(&issuer).copied(), config);
+        self.by_issuer.insert(issuer, index);
+        self
+    }
+
+    /// Route tokens whose header `host` is `host` to `config`.
+    ///
+    /// Used when a token's `iss` matches no registered issuer. The config is
+    /// used as-is, so set [`TokenValidation::issuers`](crate::config::TokenValidation::issuers)
+    /// on it to pin the accepted issuers. Registering the same host again
+    /// replaces its config.
+    pub fn register_host(&mut self, host: impl Into<String>, config: AuthConfig) -> &mut Self {
+        let host = host.into().to_ascii_lowercase();
+        let index = self.store(self.by_host.get(&host).copied(), config);
+        self.by_host.insert(host, index);
+        self
+    }
+
+    /// Builder variant of [`register`](Self::register).
+    pub fn with_issuer(mut self, issuer: impl Into<String>, config: AuthConfig) -> Self {
+        self.register(issuer, config);
+        self
+    }
+
+    /// Builder variant of [`register_host`](Self::register_host).
+    pub fn with_host(mut self, host: impl Into<String>, config: AuthConfig) -> Self {
+        self.register_host(host, config);
+        self
+    }
+
+    /// Store `config` at `index`, replacing the config there, or append it.
+    fn store(&mut self, index: Option<usize>, config: AuthConfig) -> usize {
+        match index {
+            Some(index) => {
+                self.configs[index] = config;
+                index
+            }
+            None => {
+                self.configs.push(config);
+                self.configs.len() - 1
+            }
+        }
+    }
+
+    /// Every registered config.
+    pub fn configs(&self) -> impl Iterator<Item = &AuthConfig> {
+        self.configs.iter()
+    }
+
+    /// Returns `true` if no issuer or host is registered.
+    pub fn is_empty(&self) -> bool {
+        self.by_issuer.is_empty() && self.by_host.is_empty()
+    }
+
+    /// The config registered for `issuer`.
+    pub fn get(&self, issuer: &str) -> Option<&AuthConfig> {
+        self.by_issuer.get(issuer).map(|&index| &self.configs[index])
+    }
+
+    /// The config registered for the header `host`.
+    pub fn get_by_host(&self, host: &str) -> Option<&AuthConfig> {
+        self.by_host
+            .get(&host.to_ascii_lowercase())
+            .map(|&index| &self.configs[index])
+    }
+
+    /// Select the config for `token` by its `iss` claim, falling back to the
+    /// `host` header field.
+    ///
+    /// Returns `USSOError::UnknownIssuer` when neither is registered.
+    pub fn route(&self, token: &str) -> Result<&AuthConfig, USSOError> {
+        let issuer = unverified_issuer(token)?;
+        if let Some(config) = issuer.as_deref().and_then(|iss| self.get(iss)) {
+            return Ok(config);
+        }
+        let header = JwtHeader::from_token(token)?;
+        if let Some(config) = header
+            .host
+            .as_deref()
+            .and_then(|host| self.get_by_host(host))
+        {
+            return Ok(config);
+        }
+        Err(USSOError::UnknownIssuer(
+            issuer.or(header.host).unwrap_or_else(|| "-".into()),
+        ))
+    }
+}
+
+/// Read the `iss` claim without verifying the token.
+fn unverified_issuer(token: &str) -> Result<Option<String>, USSOError> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| USSOError::MalformedToken("missing payload".into()))?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| USSOError::MalformedToken("payload is not valid base64url".into()))?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes)
+        .map_err(|_| USSOError::MalformedToken("payload is not a JSON object".into()))?;
+    Ok(claims
+        .get("iss")
+        .and_then(|iss| iss.as_str())
+        .map(String::from))
+}
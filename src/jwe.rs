@@ -0,0 +1,312 @@
+//! Decryption of compact JWE (encrypted) tokens.
+//!
+//! USSO can issue tokens as a JWS nested inside a JWE. [`decrypt_to_jws`]
+//! unwraps the JWE with a service-held [`DecryptionKey`] and returns the inner
+//! JWS, which is then verified like any other token (see
+//! [`decode_token`](crate::core::decode_token)). [`UssoAuth`](crate::core::UssoAuth)
+//! does this automatically once keys are registered with
+//! [`with_decryption_keys`](crate::core::UssoAuth::with_decryption_keys).
+//!
+//! Supported key management algorithms (`alg`):
+//! - **RSA-OAEP-256** with an RSA private key
+//! - **ECDH-ES** and **ECDH-ES+A256KW** with a P-256 private key
+//!
+//! Supported content encryption (`enc`): **A256GCM**.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::core::decode_token;
+//! use usso::jwe::{decrypt_to_jws, DecryptionKey};
+//! # fn example(jwk: usso::schemas::Jwk, token: &str) -> Result<(), Box<dyn std::error::Error>> {
+//! let key = DecryptionKey::from_pem(&std::fs::read_to_string("jwe-key.pem")?)?;
+//! let jws = decrypt_to_jws(token, &[key])?;
+//! let user = decode_token(&jwk, &jws)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::Engine;
+use p256::pkcs8::DecodePrivateKey;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::{Oaep, RsaPrivateKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::exceptions::{JwtError, USSOError};
+
+/// A private key used to decrypt JWE tokens.
+#[derive(Debug, Clone)]
+pub struct DecryptionKey {
+    /// Matched against the JWE header `kid`; keys without a `kid` are tried
+    /// for any token.
+    pub kid: Option<String>,
+    material: KeyMaterial,
+}
+
+#[derive(Debug, Clone)]
+enum KeyMaterial {
+    Rsa(Box<RsaPrivateKey>),
+    P256(p256::SecretKey),
+}
+
+impl DecryptionKey {
+    /// Wrap an RSA private key (for `RSA-OAEP-256`).
+    pub fn rsa(key: RsaPrivateKey) -> Self {
+        DecryptionKey {
+            kid: None,
+            material: KeyMaterial::Rsa(Box::new(key)),
+        }
+    }
+
+    /// Wrap a P-256 private key (for `ECDH-ES` and `ECDH-ES+A256KW`).
+    pub fn p256(key: p256::SecretKey) -> Self {
+        DecryptionKey {
+            kid: None,
+            material: KeyMaterial::P256(key),
+        }
+    }
+
+    /// Parse a PEM-encoded RSA (PKCS#1 or PKCS#8) or P-256 (SEC1 or PKCS#8)
+    /// private key.
+    pub fn from_pem(pem: &str) -> Result<Self, USSOError> {
+        if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(pem) {
+            return Ok(Self::rsa(key));
+        }
+        if let Ok(key) = RsaPrivateKey::from_pkcs1_pem(pem) {
+            return Ok(Self::rsa(key));
+        }
+        if let Ok(key) = p256::SecretKey::from_pkcs8_pem(pem) {
+            return Ok(Self::p256(key));
+        }
+        p256::SecretKey::from_sec1_pem(pem)
+            .map(Self::p256)
+            .map_err(|_| {
+                USSOError::InvalidKey("expected an RSA or P-256 private key in PEM format".into())
+            })
+    }
+
+    /// Set the `kid` this key is selected by.
+    pub fn with_kid(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    fn supports(&self, alg: &str) -> bool {
+        match &self.material {
+            KeyMaterial::Rsa(_) => alg == "RSA-OAEP-256",
+            KeyMaterial::P256(_) => matches!(alg, "ECDH-ES" | "ECDH-ES+A256KW"),
+        }
+    }
+}
+
+/// The protected header of a compact JWE.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JweHeader {
+    pub alg: String,
+    pub enc: String,
+    pub kid: Option<String>,
+    pub cty: Option<String>,
+    pub zip: Option<String>,
+    pub epk: Option<EphemeralPublicKey>,
+    pub apu: Option<String>,
+    pub apv: Option<String>,
+}
+
+/// The sender's ephemeral public key (`epk`) of an ECDH-ES JWE.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EphemeralPublicKey {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    pub y: String,
+}
+
+/// Returns `true` if `token` looks like a compact JWE (five dot-separated parts).
+pub fn is_jwe(token: &str) -> bool {
+    token.trim().split('.').count() == 5
+}
+
+/// Decrypt a compact JWE and return its plaintext.
+///
+/// When the header names a `kid`, keys with that `kid` are tried first, then
+/// keys without a `kid`; otherwise every key supporting the `alg` is tried.
+pub fn decrypt(token: &str, keys: &[DecryptionKey]) -> Result<Vec<u8>, USSOError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let [protected, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+        return Err(USSOError::MalformedToken(
+            "expected five dot-separated JWE parts".into(),
+        ));
+    };
+    let header: JweHeader = serde_json::from_slice(&decode_part(protected, "header")?)
+        .map_err(|_| JwtError::DecodingError("JWE header is not valid JSON".into()))?;
+
+    if header.enc != "A256GCM" {
+        return Err(USSOError::UnsupportedAlgorithm(header.enc));
+    }
+    if !matches!(
+        header.alg.as_str(),
+        "RSA-OAEP-256" | "ECDH-ES" | "ECDH-ES+A256KW"
+    ) {
+        return Err(USSOError::UnsupportedAlgorithm(header.alg));
+    }
+    if header.zip.is_some() {
+        return Err(USSOError::UnsupportedAlgorithm("zip".into()));
+    }
+
+    let encrypted_key = decode_part(encrypted_key, "encrypted key")?;
+    let iv = decode_part(iv, "iv")?;
+    let mut sealed = decode_part(ciphertext, "ciphertext")?;
+    sealed.extend(decode_part(tag, "tag")?);
+    if iv.len() != 12 {
+        return Err(USSOError::MalformedToken(
+            "A256GCM requires a 96-bit iv".into(),
+        ));
+    }
+
+    let candidates = candidate_keys(keys, &header);
+    if candidates.is_empty() {
+        return Err(match header.kid {
+            Some(kid) => USSOError::UnknownKey(kid),
+            None => USSOError::DecryptionFailed(format!("no decryption key for {}", header.alg)),
+        });
+    }
+
+    let mut error = None;
+    for key in candidates {
+        let result = content_encryption_key(key, &header, &encrypted_key).and_then(|cek| {
+            let cipher = Aes256Gcm::new_from_slice(&cek)
+                .map_err(|_| USSOError::DecryptionFailed("invalid content key".into()))?;
+            let payload = Payload {
+                msg: &sealed,
+                aad: protected.as_bytes(),
+            };
+            cipher
+                .decrypt(Nonce::from_slice(&iv), payload)
+                .map_err(|_| USSOError::DecryptionFailed("authentication tag mismatch".into()))
+        });
+        match result {
+            Ok(plaintext) => return Ok(plaintext),
+            Err(err) => error = Some(err),
+        }
+    }
+    Err(error.unwrap_or(USSOError::DecryptionFailed("no usable key".into())))
+}
+
+/// Decrypt a compact JWE whose payload is a nested JWS and return that JWS.
+pub fn decrypt_to_jws(token: &str, keys: &[DecryptionKey]) -> Result<String, USSOError> {
+    let plaintext = decrypt(token, keys)?;
+    let jws = String::from_utf8(plaintext)
+        .map_err(|_| USSOError::MalformedToken("JWE payload is not a nested JWT".into()))?;
+    if jws.split('.').count() != 3 {
+        return Err(USSOError::MalformedToken(
+            "JWE payload is not a nested JWT".into(),
+        ));
+    }
+    Ok(jws)
+}
+
+fn decode_part(part: &str, name: &str) -> Result<Vec<u8>, USSOError> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| USSOError::MalformedToken(format!("JWE {name} is not valid base64url")))
+}
+
+fn candidate_keys<'a>(keys: &'a [DecryptionKey], header: &JweHeader) -> Vec<&'a DecryptionKey> {
+    let usable = keys.iter().filter(|key| key.supports(&header.alg));
+    if header.kid.is_none() {
+        return usable.collect();
+    }
+    let (matching, unnamed): (Vec<_>, Vec<_>) = usable
+        .filter(|key| key.kid.is_none() || key.kid == header.kid)
+        .partition(|key| key.kid.is_some());
+    matching.into_iter().chain(unnamed).collect()
+}
+
+/// Recover the content encryption key according to the header `alg`.
+fn content_encryption_key(
+    key: &DecryptionKey,
+    header: &JweHeader,
+    encrypted_key: &[u8],
+) -> Result<Vec<u8>, USSOError> {
+    match (&key.material, header.alg.as_str()) {
+        (KeyMaterial::Rsa(private), "RSA-OAEP-256") => private
+            .decrypt(Oaep::new::<Sha256>(), encrypted_key)
+            .map_err(|_| USSOError::DecryptionFailed("RSA-OAEP-256 unwrap failed".into())),
+        (KeyMaterial::P256(private), "ECDH-ES") => {
+            if !encrypted_key.is_empty() {
+                return Err(USSOError::MalformedToken(
+                    "ECDH-ES tokens must have an empty encrypted key".into(),
+                ));
+            }
+            ecdh_es_key(private, header, &header.enc)
+        }
+        (KeyMaterial::P256(private), "ECDH-ES+A256KW") => {
+            let kek = ecdh_es_key(private, header, &header.alg)?;
+            let kek = aes_kw::KekAes256::try_from(kek.as_slice())
+                .map_err(|_| USSOError::DecryptionFailed("invalid key wrapping key".into()))?;
+            let mut cek = vec![0u8; encrypted_key.len().saturating_sub(8)];
+            kek.unwrap(encrypted_key, &mut cek)
+                .map_err(|_| USSOError::DecryptionFailed("A256KW unwrap failed".into()))?;
+            Ok(cek)
+        }
+        _ => Err(USSOError::UnsupportedAlgorithm(header.alg.clone())),
+    }
+}
+
+/// Derive a 256-bit key from the ECDH shared secret with the Concat KDF
+/// (RFC 7518, section 4.6.2).
+fn ecdh_es_key(
+    private: &p256::SecretKey,
+    header: &JweHeader,
+    algorithm_id: &str,
+) -> Result<Vec<u8>, USSOError> {
+    let epk = header
+        .epk
+        .as_ref()
+        .ok_or_else(|| USSOError::MalformedToken("ECDH-ES header has no epk".into()))?;
+    if epk.kty != "EC" || epk.crv != "P-256" {
+        return Err(USSOError::KeyMismatch(format!(
+            "ephemeral key {} {} does not match a P-256 key",
+            epk.kty, epk.crv
+        )));
+    }
+    let mut point = vec![0x04u8];
+    point.extend(decode_part(&epk.x, "epk x")?);
+    point.extend(decode_part(&epk.y, "epk y")?);
+    let public = p256::PublicKey::from_sec1_bytes(&point)
+        .map_err(|_| USSOError::MalformedToken("epk is not a P-256 point".into()))?;
+    let shared = p256::ecdh::diffie_hellman(private.to_nonzero_scalar(), public.as_affine());
+
+    let apu = header
+        .apu
+        .as_deref()
+        .map(|v| decode_part(v, "apu"))
+        .transpose()?;
+    let apv = header
+        .apv
+        .as_deref()
+        .map(|v| decode_part(v, "apv"))
+        .transpose()?;
+    Ok(concat_kdf(
+        shared.raw_secret_bytes(),
+        algorithm_id.as_bytes(),
+        apu.as_deref().unwrap_or_default(),
+        apv.as_deref().unwrap_or_default(),
+    ))
+}
+
+fn concat_kdf(z: &[u8], algorithm_id: &[u8], apu: &[u8], apv: &[u8]) -> Vec<u8> {
+    let length_prefixed = |data: &[u8]| [&(data.len() as u32).to_be_bytes()[..], data].concat();
+    Sha256::new()
+        .chain_update(1u32.to_be_bytes())
+        .chain_update(z)
+        .chain_update(length_prefixed(algorithm_id))
+        .chain_update(length_prefixed(apu))
+        .chain_update(length_prefixed(apv))
+        .chain_update(256u32.to_be_bytes())
+        .finalize()
+        .to_vec()
+}
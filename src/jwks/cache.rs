@@ -0,0 +1,351 @@
+//! Refreshable, per-URL JWKS cache.
+//!
+//! A [`JwksCache`] maps JWKS URLs to their most recently fetched key set.
+//! Each entry expires after the lifetime advertised by the server's caching
+//! headers (clamped to [`min_ttl`](JwksCacheConfig::min_ttl) and
+//! [`max_ttl`](JwksCacheConfig::max_ttl)) or, without such headers, after the
+//! configured [`ttl`](JwksCacheConfig::ttl). Entries can be invalidated manually,
+//! and are replaced atomically on refresh so readers never observe a partially
+//! updated key set. Tokens signed with an unknown `kid` and lookups of an
+//! expired entry trigger at most one refetch per
+//! [`min_refetch_interval`](JwksCacheConfig::min_refetch_interval), so key
+//! rotation is picked up without letting forged `kid` values or concurrent
+//! requests flood the identity server.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::jwks::cache::JwksCache;
+//!
+//! let cache = JwksCache::default();
+//! let keys = cache.get_or_fetch_sync("https://sso.usso.io/website/jwks.json").unwrap();
+//! println!("Loaded {} keys", keys.keys.len());
+//!
+//! // Force the next lookup to hit the network again.
+//! cache.invalidate("https://sso.usso.io/website/jwks.json");
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{
+    Arc, Mutex, MutexGuard, OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+use std::time::{Duration, Instant};
+
+use crate::exceptions::JwksError;
+use crate::jwks::{fetch_conditional_async, fetch_conditional_sync, HttpCacheInfo, JwksFetch};
+use crate::schemas::{Jwk, Jwks};
+
+/// Configuration for a [`JwksCache`].
+#[derive(Debug, Clone)]
+pub struct JwksCacheConfig {
+    /// How long a fetched key set is considered fresh when the response has
+    /// no `Cache-Control: max-age` or `Expires` header.
+    pub ttl: Duration,
+    /// Lower bound applied to the lifetime derived from caching headers.
+    pub min_ttl: Duration,
+    /// Upper bound applied to the lifetime derived from caching headers.
+    pub max_ttl: Duration,
+    /// Minimum time between two fetches of the same URL triggered by an
+    /// unknown `kid` or an expired entry.
+    pub min_refetch_interval: Duration,
+}
+
+impl Default for JwksCacheConfig {
+    fn default() -> Self {
+        JwksCacheConfig {
+            ttl: Duration::from_secs(3600),
+            min_ttl: Duration::from_secs(60),
+            max_ttl: Duration::from_secs(24 * 3600),
+            min_refetch_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A key set stored in a [`JwksCache`] together with its fetch metadata.
+#[derive(Debug, Clone)]
+pub struct CachedJwks {
+    pub keys: Arc<Jwks>,
+    pub fetched_at: Instant,
+    pub expires_at: Instant,
+    pub http: HttpCacheInfo,
+}
+
+impl CachedJwks {
+    /// Returns `true` once the entry has outlived its TTL.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+}
+
+/// A thread-safe JWKS cache keyed by JWKS URL.
+///
+/// Lookups through [`get`](Self::get) only return fresh entries, while
+/// [`get_or_fetch_sync`](Self::get_or_fetch_sync) and
+/// [`get_or_fetch_async`](Self::get_or_fetch_async) transparently refetch
+/// missing or expired key sets, falling back to the expired key set while the
+/// server cannot be reached.
+#[derive(Debug, Default)]
+pub struct JwksCache {
+    config: JwksCacheConfig,
+    entries: RwLock<HashMap<String, CachedJwks>>,
+    last_fetch_attempts: Mutex<HashMap<String, Instant>>,
+    refresh_intervals: Mutex<HashMap<String, Duration>>,
+    http_client: reqwest::Client,
+    /// Built on first blocking use, so async-only callers never start its
+    /// runtime thread.
+    blocking_client: OnceLock<reqwest::blocking::Client>,
+}
+
+impl JwksCache {
+    /// Create an empty cache with the given configuration.
+    pub fn new(config: JwksCacheConfig) -> Self {
+        JwksCache {
+            config,
+            entries: RwLock::new(HashMap::new()),
+            last_fetch_attempts: Mutex::new(HashMap::new()),
+            refresh_intervals: Mutex::new(HashMap::new()),
+            http_client: reqwest::Client::new(),
+            blocking_client: OnceLock::new(),
+        }
+    }
+
+    /// Return the configuration this cache was created with.
+    pub fn config(&self) -> &JwksCacheConfig {
+        &self.config
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, CachedJwks>> {
+        self.entries.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, CachedJwks>> {
+        self.entries.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return the cached key set for `jwk_url` if present and not expired.
+    pub fn get(&self, jwk_url: &str) -> Option<Arc<Jwks>> {
+        self.read()
+            .get(jwk_url)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.keys.clone())
+    }
+
+    /// Return the cached key set for `jwk_url` even if it has expired.
+    pub fn peek(&self, jwk_url: &str) -> Option<Arc<Jwks>> {
+        self.read().get(jwk_url).map(|entry| entry.keys.clone())
+    }
+
+    /// Return the full cache entry (keys and timestamps) for `jwk_url`.
+    pub fn entry(&self, jwk_url: &str) -> Option<CachedJwks> {
+        self.read().get(jwk_url).cloned()
+    }
+
+    /// Store `jwks` for `jwk_url`, atomically replacing any previous key set.
+    ///
+    /// The entry expires after the configured [`ttl`](JwksCacheConfig::ttl).
+    pub fn insert(&self, jwk_url: &str, jwks: Jwks) -> Arc<Jwks> {
+        self.store(jwk_url, Arc::new(jwks), HttpCacheInfo::default())
+    }
+
+    /// Lifetime for an entry whose response carried the given caching headers.
+    pub fn ttl_for(&self, http: &HttpCacheInfo) -> Duration {
+        match http.ttl() {
+            Some(ttl) => ttl.clamp(
+                self.config.min_ttl,
+                self.config.max_ttl.max(self.config.min_ttl),
+            ),
+            None => self.config.ttl,
+        }
+    }
+
+    fn refresh_intervals(&self) -> MutexGuard<'_, HashMap<String, Duration>> {
+        self.refresh_intervals
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keep entries for `jwk_url` alive for at least `interval`, because a
+    /// background refresher renews them on that schedule.
+    pub(crate) fn set_refresh_interval(&self, jwk_url: &str, interval: Duration) {
+        self.refresh_intervals()
+            .insert(jwk_url.to_string(), interval);
+    }
+
+    /// Stop applying the refresher interval registered for `jwk_url`.
+    pub(crate) fn clear_refresh_interval(&self, jwk_url: &str) {
+        self.refresh_intervals().remove(jwk_url);
+    }
+
+    fn store(&self, jwk_url: &str, keys: Arc<Jwks>, http: HttpCacheInfo) -> Arc<Jwks> {
+        let now = Instant::now();
+        let mut ttl = self.ttl_for(&http);
+        if let Some(interval) = self.refresh_intervals().get(jwk_url) {
+            ttl = ttl.max(*interval);
+        }
+        let expires_at = now + ttl;
+        self.write().insert(
+            jwk_url.to_string(),
+            CachedJwks {
+                keys: keys.clone(),
+                fetched_at: now,
+                expires_at,
+                http,
+            },
+        );
+        keys
+    }
+
+    fn apply_fetch(
+        &self,
+        jwk_url: &str,
+        previous: Option<CachedJwks>,
+        fetch: JwksFetch,
+    ) -> Result<Arc<Jwks>, JwksError> {
+        match (fetch, previous) {
+            (JwksFetch::Modified { jwks, cache }, _) => {
+                Ok(self.store(jwk_url, Arc::new(jwks), cache))
+            }
+            (JwksFetch::NotModified { cache }, Some(previous)) => {
+                let http = previous.http.merge_not_modified(cache);
+                Ok(self.store(jwk_url, previous.keys, http))
+            }
+            // The entry was invalidated while the request was in flight.
+            (JwksFetch::NotModified { .. }, None) => Err(JwksError::InvalidJwksData(
+                "304 Not Modified for a key set that is no longer cached".into(),
+            )),
+        }
+    }
+
+    /// Drop the cached key set for `jwk_url`.
+    ///
+    /// Returns `true` if an entry was removed.
+    pub fn invalidate(&self, jwk_url: &str) -> bool {
+        self.write().remove(jwk_url).is_some()
+    }
+
+    /// Drop every cached key set.
+    pub fn clear(&self) {
+        self.write().clear();
+    }
+
+    fn record_fetch_attempt(&self, jwk_url: &str) {
+        self.last_fetch_attempts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(jwk_url.to_string(), Instant::now());
+    }
+
+    /// Claim the right to refetch `jwk_url` for an unknown `kid` or an expired entry.
+    ///
+    /// Returns `false` if any fetch of this URL was attempted within the last
+    /// [`min_refetch_interval`](JwksCacheConfig::min_refetch_interval).
+    fn try_claim_refetch(&self, jwk_url: &str) -> bool {
+        let mut attempts = self
+            .last_fetch_attempts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        match attempts.get(jwk_url) {
+            Some(last) if now.duration_since(*last) < self.config.min_refetch_interval => false,
+            _ => {
+                attempts.insert(jwk_url.to_string(), now);
+                true
+            }
+        }
+    }
+
+    /// Fetch `jwk_url` (blocking) and replace the cached key set.
+    ///
+    /// If a key set is already cached, the request is conditional and a
+    /// `304 Not Modified` keeps the existing keys with a renewed lifetime.
+    pub fn refresh_sync(&self, jwk_url: &str) -> Result<Arc<Jwks>, JwksError> {
+        self.record_fetch_attempt(jwk_url);
+        let previous = self.entry(jwk_url);
+        let client = self
+            .blocking_client
+            .get_or_init(reqwest::blocking::Client::new);
+        let fetch = fetch_conditional_sync(client, jwk_url, previous.as_ref().map(|e| &e.http))?;
+        self.apply_fetch(jwk_url, previous, fetch)
+    }
+
+    /// Fetch `jwk_url` (async) and replace the cached key set.
+    ///
+    /// See [`refresh_sync`](Self::refresh_sync).
+    pub async fn refresh_async(&self, jwk_url: &str) -> Result<Arc<Jwks>, JwksError> {
+        self.record_fetch_attempt(jwk_url);
+        let previous = self.entry(jwk_url);
+        let previous_http = previous.as_ref().map(|e| &e.http);
+        let fetch = fetch_conditional_async(&self.http_client, jwk_url, previous_http).await?;
+        self.apply_fetch(jwk_url, previous, fetch)
+    }
+
+    /// Return the fresh cached key set, fetching it (blocking) if missing or expired.
+    ///
+    /// An expired key set is refetched at most once per
+    /// [`min_refetch_interval`](JwksCacheConfig::min_refetch_interval); while
+    /// a refetch is in flight, was just attempted, or fails, the
+    /// last-known-good keys are returned instead.
+    pub fn get_or_fetch_sync(&self, jwk_url: &str) -> Result<Arc<Jwks>, JwksError> {
+        if let Some(keys) = self.get(jwk_url) {
+            return Ok(keys);
+        }
+        let Some(stale) = self.peek(jwk_url) else {
+            return self.refresh_sync(jwk_url);
+        };
+        if !self.try_claim_refetch(jwk_url) {
+            return Ok(stale);
+        }
+        Ok(self.refresh_sync(jwk_url).unwrap_or(stale))
+    }
+
+    /// Return the fresh cached key set, fetching it (async) if missing or expired.
+    ///
+    /// See [`get_or_fetch_sync`](Self::get_or_fetch_sync).
+    pub async fn get_or_fetch_async(&self, jwk_url: &str) -> Result<Arc<Jwks>, JwksError> {
+        if let Some(keys) = self.get(jwk_url) {
+            return Ok(keys);
+        }
+        let Some(stale) = self.peek(jwk_url) else {
+            return self.refresh_async(jwk_url).await;
+        };
+        if !self.try_claim_refetch(jwk_url) {
+            return Ok(stale);
+        }
+        Ok(self.refresh_async(jwk_url).await.unwrap_or(stale))
+    }
+
+    /// Find the key with the given `kid`, refetching the key set (blocking)
+    /// once if the `kid` is unknown.
+    ///
+    /// The refetch is skipped when the URL was fetched less than
+    /// [`min_refetch_interval`](JwksCacheConfig::min_refetch_interval) ago, in
+    /// which case `JwksError::UnknownKid` is returned straight away.
+    pub fn find_key_sync(&self, jwk_url: &str, kid: &str) -> Result<Jwk, JwksError> {
+        let keys = self.get_or_fetch_sync(jwk_url)?;
+        if let Some(key) = keys.match_kid(kid) {
+            return Ok(key.clone());
+        }
+        if !self.try_claim_refetch(jwk_url) {
+            return Err(JwksError::UnknownKid(kid.to_string()));
+        }
+        let keys = self.refresh_sync(jwk_url)?;
+        keys.match_kid(kid)
+            .cloned()
+            .ok_or_else(|| JwksError::UnknownKid(kid.to_string()))
+    }
+
+    /// Async variant of [`find_key_sync`](Self::find_key_sync).
+    pub async fn find_key_async(&self, jwk_url: &str, kid: &str) -> Result<Jwk, JwksError> {
+        let keys = self.get_or_fetch_async(jwk_url).await?;
+        if let Some(key) = keys.match_kid(kid) {
+            return Ok(key.clone());
+        }
+        if !self.try_claim_refetch(jwk_url) {
+            return Err(JwksError::UnknownKid(kid.to_string()));
+        }
+        let keys = self.refresh_async(jwk_url).await?;
+        keys.match_kid(kid)
+            .cloned()
+            .ok_or_else(|| JwksError::UnknownKid(kid.to_string()))
+    }
+}
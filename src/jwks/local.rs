@@ -0,0 +1,179 @@
+//! Loading key sets from local sources, without any HTTP.
+//!
+//! For air-gapped deployments and tests, keys can come from a JWKS JSON file,
+//! a directory of PEM public keys, or an environment variable. The resulting
+//! [`Jwks`] can be passed inline to [`Usso::new`](crate::core::Usso::new),
+//! [`JWTConfig::new`](crate::schemas::JWTConfig::new) or
+//! [`AuthConfig::from_keys`](crate::config::AuthConfig::from_keys).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::config::AuthConfig;
+//! use usso::core::UssoAuth;
+//! use usso::jwks::local::load_pem_dir;
+//!
+//! // `keys/2024-signing.pem` becomes the key with kid `2024-signing`.
+//! let keys = load_pem_dir("keys").unwrap();
+//! let auth = UssoAuth::new(Some(AuthConfig::from_keys(keys)), None);
+//! ```
+
+use std::path::Path;
+
+use base64::Engine;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use spki::der::asn1::ObjectIdentifier;
+use spki::der::DecodePem;
+use spki::SubjectPublicKeyInfoOwned;
+
+use crate::exceptions::JwksError;
+use crate::schemas::{Jwk, Jwks};
+
+/// Conventional environment variable holding a JWKS document, for use with
+/// [`load_jwks_env`].
+pub const DEFAULT_JWKS_ENV: &str = "USSO_JWKS";
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const ED448: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.113");
+const P256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const P384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const P521: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.35");
+const SECP256K1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.10");
+
+/// Parse a JWKS JSON document, rejecting empty key sets.
+pub fn parse_jwks(json: &str) -> Result<Jwks, JwksError> {
+    let jwks: Jwks = serde_json::from_str(json)
+        .map_err(|err| JwksError::InvalidJwksData(format!("invalid JWKS JSON: {err}")))?;
+    if jwks.keys.is_empty() {
+        return Err(JwksError::InvalidJwksData("No keys found in JWKS".into()));
+    }
+    Ok(jwks)
+}
+
+/// Load a JWKS JSON file.
+pub fn load_jwks_file(path: impl AsRef<Path>) -> Result<Jwks, JwksError> {
+    parse_jwks(&std::fs::read_to_string(path)?)
+}
+
+/// Load a JWKS JSON document from the environment variable `var`.
+pub fn load_jwks_env(var: &str) -> Result<Jwks, JwksError> {
+    let json =
+        std::env::var(var).map_err(|_| JwksError::InvalidJwksData(format!("{var} is not set")))?;
+    parse_jwks(&json)
+}
+
+/// Load every `*.pem` public key in `dir`, using the file stem as `kid`.
+///
+/// Keys are returned sorted by `kid`.
+pub fn load_pem_dir(dir: impl AsRef<Path>) -> Result<Jwks, JwksError> {
+    let mut keys = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "pem") {
+            let kid = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| {
+                    JwksError::InvalidJwksData(format!("invalid key file name: {}", path.display()))
+                })?;
+            keys.push(jwk_from_pem(&std::fs::read_to_string(&path)?, kid)?);
+        }
+    }
+    if keys.is_empty() {
+        return Err(JwksError::InvalidJwksData("No keys found in JWKS".into()));
+    }
+    keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+    Ok(Jwks { keys })
+}
+
+/// Convert a PEM public key into a signing [`Jwk`] with the given `kid`.
+///
+/// Accepts RSA (`PUBLIC KEY` or `RSA PUBLIC KEY`), EC P-256/P-384/P-521/secp256k1
+/// and Ed25519/Ed448 public keys. EC and EdDSA keys get their `alg` set; RSA
+/// keys are left without one since they may be used with RS* or PS*.
+pub fn jwk_from_pem(pem: &str, kid: &str) -> Result<Jwk, JwksError> {
+    if let Ok(rsa) = RsaPublicKey::from_pkcs1_pem(pem) {
+        return Ok(rsa_jwk(&rsa, kid));
+    }
+    let info = SubjectPublicKeyInfoOwned::from_pem(pem)
+        .map_err(|_| JwksError::InvalidJwksData(format!("key {kid}: not a PEM public key")))?;
+    jwk_from_spki(&info, kid)
+}
+
+/// Convert a DER `SubjectPublicKeyInfo` (as found in PEM keys and X.509
+/// certificates) into a signing [`Jwk`]; see [`jwk_from_pem`].
+pub fn jwk_from_spki(info: &SubjectPublicKeyInfoOwned, kid: &str) -> Result<Jwk, JwksError> {
+    let invalid = |reason: &str| JwksError::InvalidJwksData(format!("key {kid}: {reason}"));
+    let b64 = |bytes: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    let key_bytes = info
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| invalid("malformed public key bits"))?;
+
+    if info.algorithm.oid == RSA_ENCRYPTION {
+        let rsa = RsaPublicKey::from_pkcs1_der(key_bytes)
+            .map_err(|_| invalid("malformed RSA public key"))?;
+        return Ok(rsa_jwk(&rsa, kid));
+    }
+
+    let mut jwk = Jwk {
+        kid: kid.to_string(),
+        r#use: Some("sig".into()),
+        ..Default::default()
+    };
+    if info.algorithm.oid == ED25519 || info.algorithm.oid == ED448 {
+        let crv = if info.algorithm.oid == ED25519 {
+            "Ed25519"
+        } else {
+            "Ed448"
+        };
+        jwk.kty = "OKP".into();
+        jwk.alg = Some("EdDSA".into());
+        jwk.crv = Some(crv.into());
+        jwk.x = Some(b64(key_bytes));
+        return Ok(jwk);
+    }
+    if info.algorithm.oid != EC_PUBLIC_KEY {
+        return Err(invalid("unsupported key type"));
+    }
+
+    let curve: ObjectIdentifier = info
+        .algorithm
+        .parameters
+        .as_ref()
+        .and_then(|params| params.decode_as().ok())
+        .ok_or_else(|| invalid("missing EC curve"))?;
+    let (crv, alg, size) = match curve {
+        P256 => ("P-256", "ES256", 32),
+        P384 => ("P-384", "ES384", 48),
+        P521 => ("P-521", "ES512", 66),
+        SECP256K1 => ("secp256k1", "ES256K", 32),
+        _ => return Err(invalid("unsupported EC curve")),
+    };
+    let point = match key_bytes.split_first() {
+        Some((0x04, point)) if point.len() == 2 * size => point,
+        _ => return Err(invalid("EC keys must use uncompressed points")),
+    };
+    jwk.kty = "EC".into();
+    jwk.alg = Some(alg.into());
+    jwk.crv = Some(crv.into());
+    jwk.x = Some(b64(&point[..size]));
+    jwk.y = Some(b64(&point[size..]));
+    Ok(jwk)
+}
+
+fn rsa_jwk(rsa: &RsaPublicKey, kid: &str) -> Jwk {
+    let b64 = |bytes: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    Jwk {
+        kid: kid.to_string(),
+        kty: "RSA".into(),
+        r#use: Some("sig".into()),
+        n: Some(b64(&rsa.n().to_bytes_be())),
+        e: Some(b64(&rsa.e().to_bytes_be())),
+        ..Default::default()
+    }
+}
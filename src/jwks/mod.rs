@@ -0,0 +1,326 @@
+//! JWKS (JSON Web Key Set) fetching and caching.
+//!
+//! Provides both synchronous and asynchronous functions to fetch JWKS from a
+//! remote URL. Keys are cached per URL in a process-wide [`JwksCache`] so that
+//! multiple token validations reuse the same key set without redundant HTTP
+//! calls, while still allowing several issuers and key rotation. The cache
+//! lifetime follows the server's `Cache-Control`/`Expires` headers and
+//! refreshes are sent as conditional requests (`ETag`/`Last-Modified`).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::jwks::init_jwks_sync;
+//!
+//! init_jwks_sync("https://sso.usso.io/website/jwks.json").unwrap();
+//! let keys = usso::jwks::get_jwk_keys().unwrap();
+//! println!("Loaded {} keys", keys.keys.len());
+//! ```
+
+pub mod cache;
+pub mod local;
+pub mod refresher;
+pub mod x509;
+
+use crate::{exceptions::JwksError, schemas::Jwks};
+use cache::JwksCache;
+use reqwest::header::{
+    HeaderMap, HeaderValue, AGE, CACHE_CONTROL, ETAG, EXPIRES, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, USER_AGENT,
+};
+use reqwest::StatusCode;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static JWKS_CACHE: OnceLock<JwksCache> = OnceLock::new();
+static DEFAULT_JWKS_URL: Mutex<Option<String>> = Mutex::new(None);
+
+/// Return the process-wide [`JwksCache`] used by the `init_jwks_*` functions
+/// and [`decode_token_with_jwks`](crate::core::decode_token_with_jwks).
+pub fn global_cache() -> &'static JwksCache {
+    JWKS_CACHE.get_or_init(JwksCache::default)
+}
+
+fn set_default_url(jwk_url: &str) {
+    *DEFAULT_JWKS_URL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(jwk_url.to_string());
+}
+
+/// Fetch JWKS from a URL and store it in the global cache (blocking).
+///
+/// May be called for several URLs, and again for the same URL to replace its
+/// key set. The last initialized URL is the one returned by [`get_jwk_keys`].
+pub fn init_jwks_sync(jwk_url: &str) -> Result<(), JwksError> {
+    global_cache().refresh_sync(jwk_url)?;
+    set_default_url(jwk_url);
+    Ok(())
+}
+
+/// Fetch JWKS from the given URL (blocking) without caching.
+///
+/// Sets a `User-Agent` header identifying the crate name, version, and repository.
+pub fn fetch_jwks_sync(jwk_url: &str) -> Result<Jwks, JwksError> {
+    match fetch_jwks_conditional_sync(jwk_url, None)? {
+        JwksFetch::Modified { jwks, .. } => Ok(jwks),
+        JwksFetch::NotModified { .. } => Err(unexpected_not_modified()),
+    }
+}
+
+/// Fetch JWKS from the given URL (blocking), honoring HTTP caching headers.
+///
+/// When `previous` carries an `ETag` or `Last-Modified` validator, the request
+/// is sent with `If-None-Match`/`If-Modified-Since` and a `304 Not Modified`
+/// response yields [`JwksFetch::NotModified`].
+pub fn fetch_jwks_conditional_sync(
+    jwk_url: &str,
+    previous: Option<&HttpCacheInfo>,
+) -> Result<JwksFetch, JwksError> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    fetch_conditional_sync(&client, jwk_url, previous)
+}
+
+/// [`fetch_jwks_conditional_sync`] with a caller-owned client, so its
+/// connection pool is reused across fetches.
+pub(crate) fn fetch_conditional_sync(
+    client: &reqwest::blocking::Client,
+    jwk_url: &str,
+    previous: Option<&HttpCacheInfo>,
+) -> Result<JwksFetch, JwksError> {
+    let response = client
+        .get(jwk_url)
+        .headers(default_headers())
+        .headers(conditional_headers(previous))
+        .send()?
+        .error_for_status()?; // Ensures that non-2xx/3xx responses are treated as errors
+
+    let cache = HttpCacheInfo::from_headers(response.headers());
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(JwksFetch::NotModified { cache });
+    }
+
+    let jwks = public_keys(response.json()?)?;
+    Ok(JwksFetch::Modified { jwks, cache })
+}
+
+/// Fetch JWKS from a URL and store it in the global cache (async).
+///
+/// May be called for several URLs, and again for the same URL to replace its
+/// key set. The last initialized URL is the one returned by [`get_jwk_keys`].
+pub async fn init_jwks_async(jwk_url: &str) -> Result<(), JwksError> {
+    global_cache().refresh_async(jwk_url).await?;
+    set_default_url(jwk_url);
+    Ok(())
+}
+
+/// Fetch JWKS from the given URL (async) without caching.
+///
+/// Sets a `User-Agent` header identifying the crate name, version, and repository.
+pub async fn fetch_jwks_async(jwk_url: &str) -> Result<Jwks, JwksError> {
+    match fetch_jwks_conditional_async(jwk_url, None).await? {
+        JwksFetch::Modified { jwks, .. } => Ok(jwks),
+        JwksFetch::NotModified { .. } => Err(unexpected_not_modified()),
+    }
+}
+
+/// Fetch JWKS from the given URL (async), honoring HTTP caching headers.
+///
+/// See [`fetch_jwks_conditional_sync`].
+pub async fn fetch_jwks_conditional_async(
+    jwk_url: &str,
+    previous: Option<&HttpCacheInfo>,
+) -> Result<JwksFetch, JwksError> {
+    let client = reqwest::Client::builder().build()?;
+    fetch_conditional_async(&client, jwk_url, previous).await
+}
+
+/// Async variant of [`fetch_conditional_sync`].
+pub(crate) async fn fetch_conditional_async(
+    client: &reqwest::Client,
+    jwk_url: &str,
+    previous: Option<&HttpCacheInfo>,
+) -> Result<JwksFetch, JwksError> {
+    let response = client
+        .get(jwk_url)
+        .headers(default_headers())
+        .headers(conditional_headers(previous))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let cache = HttpCacheInfo::from_headers(response.headers());
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(JwksFetch::NotModified { cache });
+    }
+
+    let jwks = public_keys(response.json().await?)?;
+    Ok(JwksFetch::Modified { jwks, cache })
+}
+
+/// Drop the symmetric (`oct`) keys of a fetched key set.
+///
+/// A remotely published secret is no secret: HMAC tokens are only verified
+/// with [`AuthConfig::hmac_secret`](crate::config::AuthConfig::hmac_secret).
+fn public_keys(mut jwks: Jwks) -> Result<Jwks, JwksError> {
+    jwks.keys.retain(|key| key.kty != "oct");
+    if jwks.keys.is_empty() {
+        return Err(JwksError::InvalidJwksData("No keys found in JWKS".into()));
+    }
+    Ok(jwks)
+}
+
+/// Outcome of a conditional JWKS fetch.
+#[derive(Debug, Clone)]
+pub enum JwksFetch {
+    /// The server returned a (new) key set.
+    Modified { jwks: Jwks, cache: HttpCacheInfo },
+    /// The server answered `304 Not Modified`; the previous key set is still current.
+    NotModified { cache: HttpCacheInfo },
+}
+
+/// HTTP caching metadata of a JWKS response.
+///
+/// Captures the freshness headers (`Cache-Control: max-age`, `Age`, `Expires`)
+/// and the validators (`ETag`, `Last-Modified`) used for conditional requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpCacheInfo {
+    pub max_age: Option<Duration>,
+    pub age: Option<Duration>,
+    pub expires: Option<SystemTime>,
+    pub no_cache: bool,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl HttpCacheInfo {
+    /// Parse caching metadata from response headers.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header_str = |name| {
+            headers
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+        };
+
+        let mut info = HttpCacheInfo::default();
+        if let Some(cache_control) = header_str(CACHE_CONTROL) {
+            for directive in cache_control.split(',') {
+                let directive = directive.trim().to_ascii_lowercase();
+                if directive == "no-cache" || directive == "no-store" {
+                    info.no_cache = true;
+                } else if let Some(secs) = directive.strip_prefix("max-age=") {
+                    info.max_age = secs.trim_matches('"').parse().ok().map(Duration::from_secs);
+                }
+            }
+        }
+        info.age = header_str(AGE)
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        // An unparsable `Expires` (e.g. "0") means "already expired" (RFC 9111 §5.3).
+        info.expires =
+            header_str(EXPIRES).map(|v| httpdate::parse_http_date(v.trim()).unwrap_or(UNIX_EPOCH));
+        info.etag = header_str(ETAG).map(str::to_string);
+        info.last_modified = header_str(LAST_MODIFIED).map(str::to_string);
+        info
+    }
+
+    /// Freshness lifetime derived from the headers, if any.
+    ///
+    /// `no-cache`/`no-store` yield zero, `max-age` (minus `Age`) takes
+    /// precedence over `Expires`, and `None` means the response did not say.
+    pub fn ttl(&self) -> Option<Duration> {
+        if self.no_cache {
+            return Some(Duration::ZERO);
+        }
+        if let Some(max_age) = self.max_age {
+            return Some(max_age.saturating_sub(self.age.unwrap_or_default()));
+        }
+        self.expires.map(|expires| {
+            expires
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO)
+        })
+    }
+
+    /// Whether a validator is available for a conditional request.
+    pub fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    /// Merge the headers of a `304` response into the previously stored metadata.
+    ///
+    /// Validators and freshness information missing from the `304` are kept.
+    pub fn merge_not_modified(&self, update: HttpCacheInfo) -> HttpCacheInfo {
+        let has_freshness = update.no_cache || update.max_age.is_some() || update.expires.is_some();
+        let mut merged = if has_freshness {
+            update.clone()
+        } else {
+            self.clone()
+        };
+        merged.etag = update.etag.or_else(|| self.etag.clone());
+        merged.last_modified = update.last_modified.or_else(|| self.last_modified.clone());
+        merged
+    }
+}
+
+pub(crate) fn default_headers() -> HeaderMap {
+    let user_agent = format!(
+        "{}/{} (+{})",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_REPOSITORY")
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_str(&user_agent).expect("Invalid user agent header"),
+    );
+    headers
+}
+
+fn conditional_headers(previous: Option<&HttpCacheInfo>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(previous) = previous {
+        if let Some(value) = previous
+            .etag
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_NONE_MATCH, value);
+        }
+        if let Some(value) = previous
+            .last_modified
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, value);
+        }
+    }
+    headers
+}
+
+fn unexpected_not_modified() -> JwksError {
+    JwksError::InvalidJwksData("Unexpected 304 Not Modified without validators".into())
+}
+
+/// Get the globally cached key set of the most recently initialized URL.
+///
+/// Returns `JwksError::NotInitialized` if [`init_jwks_sync`] or
+/// [`init_jwks_async`] has not been called yet.
+pub fn get_jwk_keys() -> Result<Arc<Jwks>, JwksError> {
+    let url = DEFAULT_JWKS_URL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .ok_or(JwksError::NotInitialized)?;
+    get_jwk_keys_for(&url)
+}
+
+/// Get the globally cached key set for `jwk_url`, including expired entries.
+///
+/// Returns `JwksError::NotInitialized` if the URL has never been fetched.
+pub fn get_jwk_keys_for(jwk_url: &str) -> Result<Arc<Jwks>, JwksError> {
+    global_cache()
+        .peek(jwk_url)
+        .ok_or(JwksError::NotInitialized)
+}
@@ -0,0 +1,223 @@
+//! Background JWKS refresher for async services.
+//!
+//! A [`JwksRefresher`] owns a [`JwksCache`] and refreshes one JWKS URL on a
+//! jittered interval from a tokio task, so token validation can read keys
+//! from memory without ever fetching on the request path. When the USSO
+//! server is unreachable the last successfully fetched key set keeps being
+//! served and the failure is recorded for health checks.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::jwks::refresher::{JwksRefresher, RefresherConfig};
+//!
+//! # async fn example() {
+//! let refresher = JwksRefresher::spawn(
+//!     "https://sso.usso.io/website/jwks.json",
+//!     RefresherConfig::default(),
+//! );
+//!
+//! // Later, on the request path (no network access):
+//! let key = refresher.find_key("key-id");
+//!
+//! // In a health check:
+//! let healthy = refresher.last_successful_refresh().is_some();
+//!
+//! refresher.shutdown().await;
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::exceptions::JwksError;
+use crate::jwks::cache::JwksCache;
+use crate::schemas::{Jwk, Jwks};
+
+/// Scheduling configuration for a [`JwksRefresher`].
+#[derive(Debug, Clone)]
+pub struct RefresherConfig {
+    /// Delay between two successful refreshes.
+    pub interval: Duration,
+    /// Delay before retrying after a failed refresh.
+    pub retry_interval: Duration,
+    /// Maximum random delay added to every wait, to spread refreshes of many
+    /// instances over time.
+    pub jitter: Duration,
+}
+
+impl Default for RefresherConfig {
+    fn default() -> Self {
+        RefresherConfig {
+            interval: Duration::from_secs(300),
+            retry_interval: Duration::from_secs(30),
+            jitter: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RefresherStatus {
+    pub(crate) last_success: Option<DateTime<Utc>>,
+    pub(crate) last_error: Option<String>,
+}
+
+/// A cloneable handle that stops a [`JwksRefresher`] task.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle(pub(crate) Arc<watch::Sender<bool>>);
+
+impl ShutdownHandle {
+    /// Ask the refresher task to stop after its current iteration.
+    pub fn shutdown(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+/// A tokio task that keeps the key set of one JWKS URL fresh.
+///
+/// Must be created from within a tokio runtime. The task stops on
+/// [`shutdown`](Self::shutdown), through a [`ShutdownHandle`], or once the
+/// refresher and all of its shutdown handles have been dropped.
+#[derive(Debug)]
+pub struct JwksRefresher {
+    jwk_url: String,
+    cache: Arc<JwksCache>,
+    status: Arc<Mutex<RefresherStatus>>,
+    shutdown: ShutdownHandle,
+    task: JoinHandle<()>,
+}
+
+impl JwksRefresher {
+    /// Spawn a refresher for `jwk_url` with its own default [`JwksCache`].
+    pub fn spawn(jwk_url: &str, config: RefresherConfig) -> Self {
+        Self::spawn_with_cache(jwk_url, Arc::new(JwksCache::default()), config)
+    }
+
+    /// Spawn a refresher for `jwk_url` that keeps `cache` up to date.
+    pub fn spawn_with_cache(jwk_url: &str, cache: Arc<JwksCache>, config: RefresherConfig) -> Self {
+        // Scheduled refreshes keep the entry current, so it must not expire
+        // (and be refetched on the request path) between two of them.
+        cache.set_refresh_interval(jwk_url, config.interval + config.jitter);
+        let (sender, receiver) = watch::channel(false);
+        let status = Arc::new(Mutex::new(RefresherStatus::default()));
+        let task = tokio::spawn(refresh_loop(
+            jwk_url.to_string(),
+            cache.clone(),
+            status.clone(),
+            config,
+            receiver,
+        ));
+        JwksRefresher {
+            jwk_url: jwk_url.to_string(),
+            cache,
+            status,
+            shutdown: ShutdownHandle(Arc::new(sender)),
+            task,
+        }
+    }
+
+    /// The JWKS URL being refreshed.
+    pub fn jwk_url(&self) -> &str {
+        &self.jwk_url
+    }
+
+    /// The cache kept up to date by this refresher.
+    pub fn cache(&self) -> &Arc<JwksCache> {
+        &self.cache
+    }
+
+    /// The last successfully fetched key set, even if it is past its TTL.
+    pub fn keys(&self) -> Option<Arc<Jwks>> {
+        self.cache.peek(&self.jwk_url)
+    }
+
+    /// Find a key by `kid` in the last-known-good key set without any network access.
+    pub fn find_key(&self, kid: &str) -> Result<Jwk, JwksError> {
+        let keys = self.keys().ok_or(JwksError::NotInitialized)?;
+        keys.match_kid(kid)
+            .cloned()
+            .ok_or_else(|| JwksError::UnknownKid(kid.to_string()))
+    }
+
+    fn status(&self) -> std::sync::MutexGuard<'_, RefresherStatus> {
+        self.status.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// When the key set was last fetched (or revalidated) successfully.
+    pub fn last_successful_refresh(&self) -> Option<DateTime<Utc>> {
+        self.status().last_success
+    }
+
+    /// The error of the most recent refresh, if it failed.
+    pub fn last_error(&self) -> Option<String> {
+        self.status().last_error.clone()
+    }
+
+    /// Returns `true` if no refresh has succeeded within `max_age`.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        match self.last_successful_refresh() {
+            Some(last) => chrono::Duration::from_std(max_age)
+                .map(|max_age| Utc::now() - last > max_age)
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+
+    /// A cloneable handle that can stop the refresher from elsewhere.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Stop the refresher and wait for its task to finish.
+    pub async fn shutdown(self) {
+        self.shutdown.shutdown();
+        let _ = self.task.await;
+        self.cache.clear_refresh_interval(&self.jwk_url);
+    }
+}
+
+async fn refresh_loop(
+    jwk_url: String,
+    cache: Arc<JwksCache>,
+    status: Arc<Mutex<RefresherStatus>>,
+    config: RefresherConfig,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        let result = cache.refresh_async(&jwk_url).await;
+        let delay = {
+            let mut status = status.lock().unwrap_or_else(PoisonError::into_inner);
+            match result {
+                Ok(_) => {
+                    status.last_success = Some(Utc::now());
+                    status.last_error = None;
+                    config.interval
+                }
+                // Keep serving the previous key set; it stays in the cache.
+                Err(err) => {
+                    status.last_error = Some(err.to_string());
+                    config.retry_interval
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay + random_jitter(config.jitter)) => {}
+            // Either a shutdown request or every sender was dropped.
+            _ = shutdown.wait_for(|stop| *stop) => break,
+        }
+    }
+}
+
+pub(crate) fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    let millis = rand::thread_rng().gen_range(0..=max.as_millis() as u64);
+    Duration::from_millis(millis)
+}
@@ -0,0 +1,342 @@
+//! X.509 certificate chains (`x5c`) on JSON Web Keys.
+//!
+//! Some providers publish signing keys as certificates: the JWK carries an
+//! `x5c` chain (leaf first) and possibly the `x5t`/`x5t#S256` thumbprints of
+//! the leaf, with or without the raw `n`/`e` or `x`/`y` parameters.
+//! [`resolve_key`] derives the verification key from the leaf certificate
+//! when those parameters are absent, and checks that the thumbprints and any
+//! raw parameters agree with the leaf. The chain itself is only trusted when
+//! an [`X509Validation`] is set on the
+//! [`AuthConfig`](crate::config::AuthConfig); `x5u` is never fetched.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use usso::config::AuthConfig;
+//! use usso::jwks::x509::X509Validation;
+//!
+//! let mut config = AuthConfig::new(Some("https://sso.usso.io/.well-known/jwks.json".into()));
+//! let roots = std::fs::read_to_string("roots.pem").unwrap();
+//! config.x509 = Some(X509Validation::from_pem(&roots).unwrap());
+//! ```
+
+use std::borrow::Cow;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use spki::der::asn1::ObjectIdentifier;
+use spki::der::{Decode, Encode};
+use x509_cert::ext::pkix::BasicConstraints;
+use x509_cert::Certificate;
+
+use crate::exceptions::USSOError;
+use crate::jwks::local::jwk_from_spki;
+use crate::schemas::Jwk;
+
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// Trust anchors and rules for validating the `x5c` chain of a key.
+///
+/// Each certificate in the chain must be issued and signed by the next one,
+/// and the last must either be a trust anchor or be signed by one. Issuing
+/// certificates must be CAs (`basicConstraints`). Supported certificate
+/// signatures are RSA PKCS#1 v1.5 with SHA-256/384/512, ECDSA P-256/SHA-256,
+/// ECDSA P-384/SHA-384 and Ed25519. Revocation is not checked.
+#[derive(Debug, Clone)]
+pub struct X509Validation {
+    pub trust_anchors: Vec<Certificate>,
+    /// Reject chains with a certificate outside its validity period.
+    pub check_validity: bool,
+}
+
+impl X509Validation {
+    /// Trust `trust_anchors`, checking validity periods.
+    pub fn new(trust_anchors: Vec<Certificate>) -> Self {
+        X509Validation {
+            trust_anchors,
+            check_validity: true,
+        }
+    }
+
+    /// Trust every certificate in a PEM bundle.
+    pub fn from_pem(bundle: &str) -> Result<Self, USSOError> {
+        let anchors = Certificate::load_pem_chain(bundle.as_bytes())
+            .map_err(|err| invalid(format!("invalid trust anchor PEM: {err}")))?;
+        if anchors.is_empty() {
+            return Err(invalid("no trust anchors".into()));
+        }
+        Ok(Self::new(anchors))
+    }
+
+    /// Validate the `x5c` chain of `key`.
+    ///
+    /// Keys without a chain are rejected: once trust anchors are configured,
+    /// every key has to be certified by one of them.
+    pub fn verify(&self, key: &Jwk) -> Result<(), USSOError> {
+        let chain = parse_chain(key)?;
+        if chain.is_empty() {
+            return Err(invalid(format!("key {} has no x5c chain", key.kid)));
+        }
+        if self.check_validity {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            for cert in &chain {
+                let validity = &cert.tbs_certificate.validity;
+                if now < validity.not_before.to_unix_duration()
+                    || now > validity.not_after.to_unix_duration()
+                {
+                    return Err(invalid(format!(
+                        "certificate {} is outside its validity period",
+                        cert.tbs_certificate.subject
+                    )));
+                }
+            }
+        }
+        for pair in chain.windows(2) {
+            verify_issued_by(&pair[0], &pair[1])?;
+        }
+
+        let last = &chain[chain.len() - 1];
+        if self.trust_anchors.contains(last) {
+            return Ok(());
+        }
+        let mut error = None;
+        for anchor in &self.trust_anchors {
+            match verify_issued_by(last, anchor) {
+                Ok(()) => return Ok(()),
+                Err(err) => error = Some(err),
+            }
+        }
+        Err(error.unwrap_or_else(|| invalid("no trust anchors".into())))
+    }
+}
+
+/// Return `key` with its verification key taken from the `x5c` leaf.
+///
+/// Keys without `x5c` are returned unchanged. Otherwise the `x5t` and
+/// `x5t#S256` thumbprints are checked against the leaf certificate, and raw
+/// key parameters, when present, must match the leaf's public key. This does
+/// not establish trust in the chain; see [`X509Validation`].
+pub fn resolve_key(key: &Jwk) -> Result<Cow<'_, Jwk>, USSOError> {
+    let Some(leaf) = key.x5c.as_ref().and_then(|chain| chain.first()) else {
+        return Ok(Cow::Borrowed(key));
+    };
+    let der = decode_cert(leaf)?;
+    check_thumbprint::<Sha1>(&der, key.x5t.as_deref(), "x5t")?;
+    check_thumbprint::<Sha256>(&der, key.x5t_s256.as_deref(), "x5t#S256")?;
+    let cert =
+        Certificate::from_der(&der).map_err(|err| invalid(format!("invalid x5c leaf: {err}")))?;
+    let derived = jwk_from_spki(&cert.tbs_certificate.subject_public_key_info, &key.kid)
+        .map_err(|err| invalid(err.to_string()))?;
+
+    if key.n.is_some() || key.x.is_some() {
+        if !same_public_key(key, &derived) {
+            return Err(USSOError::KeyMismatch(format!(
+                "key {} does not match its x5c certificate",
+                key.kid
+            )));
+        }
+        return Ok(Cow::Borrowed(key));
+    }
+    Ok(Cow::Owned(Jwk {
+        kty: derived.kty,
+        alg: key.alg.clone().or(derived.alg),
+        n: derived.n,
+        e: derived.e,
+        crv: derived.crv,
+        x: derived.x,
+        y: derived.y,
+        ..key.clone()
+    }))
+}
+
+fn invalid(reason: String) -> USSOError {
+    USSOError::InvalidCertificate(reason)
+}
+
+fn decode_cert(b64: &str) -> Result<Vec<u8>, USSOError> {
+    // `x5c` uses standard (not URL-safe) base64.
+    base64::engine::general_purpose::STANDARD
+        .decode(b64)
+        .map_err(|_| invalid("x5c entry is not valid base64".into()))
+}
+
+fn parse_chain(key: &Jwk) -> Result<Vec<Certificate>, USSOError> {
+    key.x5c
+        .iter()
+        .flatten()
+        .map(|b64| {
+            Certificate::from_der(&decode_cert(b64)?)
+                .map_err(|err| invalid(format!("invalid x5c certificate: {err}")))
+        })
+        .collect()
+}
+
+fn check_thumbprint<D: Digest>(
+    der: &[u8],
+    expected: Option<&str>,
+    name: &str,
+) -> Result<(), USSOError> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(D::digest(der));
+    if actual != expected {
+        return Err(invalid(format!("{name} does not match the x5c leaf")));
+    }
+    Ok(())
+}
+
+fn same_public_key(key: &Jwk, derived: &Jwk) -> bool {
+    // Compare decoded integers so that leading zero bytes do not matter.
+    let param = |value: &Option<String>| {
+        value.as_deref().map(|b64| {
+            let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(b64)
+                .unwrap_or_default();
+            let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+            bytes[start..].to_vec()
+        })
+    };
+    key.kty == derived.kty
+        && key.crv == derived.crv
+        && param(&key.n) == param(&derived.n)
+        && param(&key.e) == param(&derived.e)
+        && param(&key.x) == param(&derived.x)
+        && param(&key.y) == param(&derived.y)
+}
+
+/// Check that `cert` names `issuer` as its issuer, that `issuer` is a CA and
+/// that it signed `cert`.
+fn verify_issued_by(cert: &Certificate, issuer: &Certificate) -> Result<(), USSOError> {
+    let subject = &cert.tbs_certificate.subject;
+    if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return Err(invalid(format!(
+            "certificate {subject} is not issued by the next one"
+        )));
+    }
+    let is_ca = matches!(
+        issuer.tbs_certificate.get::<BasicConstraints>(),
+        Ok(Some((_, constraints))) if constraints.ca
+    );
+    if !is_ca {
+        return Err(invalid(format!(
+            "issuer {} is not a CA",
+            issuer.tbs_certificate.subject
+        )));
+    }
+    let message = cert
+        .tbs_certificate
+        .to_der()
+        .map_err(|err| invalid(err.to_string()))?;
+    let signature = cert
+        .signature
+        .as_bytes()
+        .ok_or_else(|| invalid("malformed certificate signature".into()))?;
+    let issuer_key = &issuer.tbs_certificate.subject_public_key_info;
+    let verified = match cert.signature_algorithm.oid {
+        SHA256_WITH_RSA => verify_rsa::<Sha256>(issuer_key, &message, signature),
+        SHA384_WITH_RSA => verify_rsa::<Sha384>(issuer_key, &message, signature),
+        SHA512_WITH_RSA => verify_rsa::<Sha512>(issuer_key, &message, signature),
+        ECDSA_WITH_SHA256 => verify_p256(issuer_key, &message, signature),
+        ECDSA_WITH_SHA384 => verify_p384(issuer_key, &message, signature),
+        ED25519 => verify_ed25519(issuer_key, &message, signature),
+        oid => {
+            return Err(invalid(format!(
+                "certificate {subject} uses unsupported signature algorithm {oid}"
+            )))
+        }
+    };
+    if !verified {
+        return Err(invalid(format!(
+            "certificate {subject} has an invalid signature"
+        )));
+    }
+    Ok(())
+}
+
+fn verify_rsa<D>(
+    issuer_key: &spki::SubjectPublicKeyInfoOwned,
+    message: &[u8],
+    signature: &[u8],
+) -> bool
+where
+    D: Digest + spki::der::oid::AssociatedOid,
+{
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::signature::Verifier;
+
+    let Ok(der) = issuer_key.to_der() else {
+        return false;
+    };
+    let Ok(key) = rsa::RsaPublicKey::from_public_key_der(&der) else {
+        return false;
+    };
+    let Ok(signature) = rsa::pkcs1v15::Signature::try_from(signature) else {
+        return false;
+    };
+    rsa::pkcs1v15::VerifyingKey::<D>::new(key)
+        .verify(message, &signature)
+        .is_ok()
+}
+
+fn verify_p256(
+    issuer_key: &spki::SubjectPublicKeyInfoOwned,
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    use p256::ecdsa::signature::Verifier;
+
+    let point = issuer_key.subject_public_key.raw_bytes();
+    match (
+        p256::ecdsa::VerifyingKey::from_sec1_bytes(point),
+        p256::ecdsa::Signature::from_der(signature),
+    ) {
+        (Ok(key), Ok(signature)) => key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+fn verify_p384(
+    issuer_key: &spki::SubjectPublicKeyInfoOwned,
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    use p384::ecdsa::signature::Verifier;
+
+    let point = issuer_key.subject_public_key.raw_bytes();
+    match (
+        p384::ecdsa::VerifyingKey::from_sec1_bytes(point),
+        p384::ecdsa::Signature::from_der(signature),
+    ) {
+        (Ok(key), Ok(signature)) => key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+fn verify_ed25519(
+    issuer_key: &spki::SubjectPublicKeyInfoOwned,
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    use ed25519_dalek::Verifier;
+
+    let Ok(point) = <[u8; 32]>::try_from(issuer_key.subject_public_key.raw_bytes()) else {
+        return false;
+    };
+    match (
+        ed25519_dalek::VerifyingKey::from_bytes(&point),
+        ed25519_dalek::Signature::from_slice(signature),
+    ) {
+        (Ok(key), Ok(signature)) => key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
@@ -26,7 +26,14 @@
 //! |--------|-------------|
 //! | [`core`] | JWT decoding, `Usso` and `UssoAuth` auth orchestrators |
 //! | [`config`] | `AuthConfig`, `HeaderConfig`, `APIHeaderConfig` configuration types |
-//! | [`jwks`] | JWKS fetching (sync/async) with global caching |
+//! | [`discovery`] | OpenID Connect discovery (`ProviderMetadata`) |
+//! | [`dpop`] | DPoP (RFC 9449) proof verification for sender-constrained tokens |
+//! | [`introspection`] | RFC 7662 token introspection with a cache of active tokens |
+//! | [`issuers`] | Multi-tenant issuer registry routing tokens by `iss` or `host` |
+//! | [`jwe`] | Decryption of encrypted (JWE) tokens with service-held private keys |
+//! | [`jwks`] | JWKS fetching (sync/async), a refreshable per-URL cache and a background refresher |
+//! | [`revocation`] | Revoked-token checks: in-memory TTL list and a polled USSO revocation list |
+//! | [`step_up`] | Step-up authentication requirements on `acr`, `amr`, `signing_level` and `auth_time` |
 //! | [`authorization`] | Scope-based RBAC engine (includes `owner_authorization`, `broadest_scope_filter`, `get_common_scopes`) |
 //! | [`integrations`] | Framework integrations (axum behind the `axum` feature) |
 //! | [`client`] | Full API client with session management (sync + async) |
@@ -39,9 +46,16 @@ pub mod authorization;
 pub mod client;
 pub mod config;
 pub mod core;
+pub mod discovery;
+pub mod dpop;
 pub mod exceptions;
+pub mod introspection;
+pub mod jwe;
 pub mod jwks;
+pub mod revocation;
 pub mod schemas;
 pub mod session;
+pub mod step_up;
 pub mod integrations;
+pub mod issuers;
 pub mod utils;
@@ -0,0 +1,281 @@
+//! Token revocation checks.
+//!
+//! A verified JWT is otherwise trusted until it expires, even after the user
+//! logged out or the session was revoked in USSO. A [`RevocationChecker`]
+//! given to [`UssoAuth::with_revocation_checker`](crate::core::UssoAuth::with_revocation_checker)
+//! is consulted after the signature and claims have been verified, with the
+//! token's `jti` and `session_id`; revoked tokens fail with
+//! [`USSOError::RevokedToken`](crate::exceptions::USSOError::RevokedToken).
+//!
+//! Two implementations are provided:
+//! - [`InMemoryRevocationList`]: entries added by the service itself, each
+//!   forgotten after its TTL (typically the remaining lifetime of the token).
+//! - [`RevocationPoller`]: a tokio task that periodically pulls the revocation
+//!   list from a configured URL, so checks never touch the network.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use usso::core::UssoAuth;
+//! use usso::revocation::InMemoryRevocationList;
+//!
+//! let revoked = Arc::new(InMemoryRevocationList::new());
+//! let auth = UssoAuth::new(None, Some("https://sso.usso.io".into()))
+//!     .with_revocation_checker(revoked.clone());
+//!
+//! // On logout:
+//! revoked.revoke_session("session-id", Duration::from_secs(3600));
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::jwks::default_headers;
+use crate::jwks::refresher::{random_jitter, RefresherConfig, RefresherStatus, ShutdownHandle};
+
+/// Decides whether a verified token has been revoked.
+///
+/// Called on the request path for every verified token, so implementations
+/// should answer from memory.
+pub trait RevocationChecker: Send + Sync + std::fmt::Debug {
+    /// Returns `true` if the token with this `jti`, or every token of this
+    /// session, has been revoked.
+    fn is_revoked(&self, jti: Option<&str>, session_id: Option<&str>) -> bool;
+}
+
+impl<T: RevocationChecker + ?Sized> RevocationChecker for Arc<T> {
+    fn is_revoked(&self, jti: Option<&str>, session_id: Option<&str>) -> bool {
+        (**self).is_revoked(jti, session_id)
+    }
+}
+
+/// A snapshot of revoked token IDs and sessions, as served by the USSO server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RevocationList {
+    #[serde(default)]
+    pub jtis: HashSet<String>,
+    #[serde(default)]
+    pub session_ids: HashSet<String>,
+}
+
+impl RevocationChecker for RevocationList {
+    fn is_revoked(&self, jti: Option<&str>, session_id: Option<&str>) -> bool {
+        jti.is_some_and(|jti| self.jtis.contains(jti))
+            || session_id.is_some_and(|session_id| self.session_ids.contains(session_id))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    jtis: HashMap<String, Instant>,
+    sessions: HashMap<String, Instant>,
+}
+
+impl Entries {
+    fn purge_expired(&mut self, now: Instant) {
+        self.jtis.retain(|_, expires_at| *expires_at > now);
+        self.sessions.retain(|_, expires_at| *expires_at > now);
+    }
+}
+
+/// Revoked token IDs and sessions kept in memory, each for its own TTL.
+///
+/// Expired entries are dropped lazily when new ones are added.
+#[derive(Debug, Default)]
+pub struct InMemoryRevocationList {
+    entries: RwLock<Entries>,
+}
+
+impl InMemoryRevocationList {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Revoke the token with this `jti` for `ttl`.
+    pub fn revoke_jti(&self, jti: impl Into<String>, ttl: Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+        entries.purge_expired(now);
+        entries.jtis.insert(jti.into(), now + ttl);
+    }
+
+    /// Revoke every token of the session `session_id` for `ttl`.
+    pub fn revoke_session(&self, session_id: impl Into<String>, ttl: Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+        entries.purge_expired(now);
+        entries.sessions.insert(session_id.into(), now + ttl);
+    }
+
+    /// Number of revocations that have not expired yet.
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+        let live = |map: &HashMap<String, Instant>| map.values().filter(|at| **at > now).count();
+        live(&entries.jtis) + live(&entries.sessions)
+    }
+
+    /// Returns `true` if no revocation is in effect.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RevocationChecker for InMemoryRevocationList {
+    fn is_revoked(&self, jti: Option<&str>, session_id: Option<&str>) -> bool {
+        let now = Instant::now();
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+        let revoked = |map: &HashMap<String, Instant>, id: Option<&str>| {
+            id.and_then(|id| map.get(id))
+                .is_some_and(|expires_at| *expires_at > now)
+        };
+        revoked(&entries.jtis, jti) || revoked(&entries.sessions, session_id)
+    }
+}
+
+/// A tokio task that keeps a copy of the server's [`RevocationList`].
+///
+/// The list at `url` is fetched right away and then on the jittered schedule
+/// of its [`RefresherConfig`]. When the server is unreachable the last
+/// fetched list keeps being used and the failure is recorded for health
+/// checks. Like [`JwksRefresher`](crate::jwks::refresher::JwksRefresher), it
+/// must be created from within a tokio runtime and stops on
+/// [`shutdown`](Self::shutdown) or once it and its shutdown handles are dropped.
+#[derive(Debug)]
+pub struct RevocationPoller {
+    url: String,
+    list: Arc<RwLock<Arc<RevocationList>>>,
+    status: Arc<Mutex<RefresherStatus>>,
+    shutdown: ShutdownHandle,
+    task: JoinHandle<()>,
+}
+
+impl RevocationPoller {
+    /// Spawn a poller for the revocation list at `url`.
+    ///
+    /// The list endpoint depends on the deployment, so there is no default:
+    /// `url` must serve a JSON [`RevocationList`].
+    pub fn spawn(url: &str, config: RefresherConfig) -> Self {
+        let (sender, receiver) = watch::channel(false);
+        let list = Arc::new(RwLock::new(Arc::new(RevocationList::default())));
+        let status = Arc::new(Mutex::new(RefresherStatus::default()));
+        let task = tokio::spawn(poll_loop(
+            url.to_string(),
+            list.clone(),
+            status.clone(),
+            config,
+            receiver,
+        ));
+        RevocationPoller {
+            url: url.to_string(),
+            list,
+            status,
+            shutdown: ShutdownHandle(Arc::new(sender)),
+            task,
+        }
+    }
+
+    /// The revocation list URL being polled.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The last successfully fetched revocation list (empty before the first).
+    pub fn list(&self) -> Arc<RevocationList> {
+        self.list
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn status(&self) -> std::sync::MutexGuard<'_, RefresherStatus> {
+        self.status.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// When the revocation list was last fetched successfully.
+    pub fn last_successful_refresh(&self) -> Option<DateTime<Utc>> {
+        self.status().last_success
+    }
+
+    /// The error of the most recent fetch, if it failed.
+    pub fn last_error(&self) -> Option<String> {
+        self.status().last_error.clone()
+    }
+
+    /// A cloneable handle that can stop the poller from elsewhere.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Stop the poller and wait for its task to finish.
+    pub async fn shutdown(self) {
+        self.shutdown.shutdown();
+        let _ = self.task.await;
+    }
+}
+
+impl RevocationChecker for RevocationPoller {
+    fn is_revoked(&self, jti: Option<&str>, session_id: Option<&str>) -> bool {
+        self.list().is_revoked(jti, session_id)
+    }
+}
+
+async fn fetch_list(client: &reqwest::Client, url: &str) -> Result<RevocationList, String> {
+    let response = client
+        .get(url)
+        .headers(default_headers())
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("unexpected status {}", response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|err| format!("invalid revocation list: {err}"))
+}
+
+async fn poll_loop(
+    url: String,
+    list: Arc<RwLock<Arc<RevocationList>>>,
+    status: Arc<Mutex<RefresherStatus>>,
+    config: RefresherConfig,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let client = reqwest::Client::new();
+    loop {
+        let result = fetch_list(&client, &url).await;
+        let delay = {
+            let mut status = status.lock().unwrap_or_else(PoisonError::into_inner);
+            match result {
+                Ok(fetched) => {
+                    *list.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(fetched);
+                    status.last_success = Some(Utc::now());
+                    status.last_error = None;
+                    config.interval
+                }
+                // Keep using the previous list.
+                Err(err) => {
+                    status.last_error = Some(err);
+                    config.retry_interval
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay + random_jitter(config.jitter)) => {}
+            // Either a shutdown request or every sender was dropped.
+            _ = shutdown.wait_for(|stop| *stop) => break,
+        }
+    }
+}
@@ -3,9 +3,10 @@
 //! Includes [`UserData`] (JWT claims), [`Jwk`]/[`Jwks`] (key set types),
 //! [`UserResponse`] (full user profile), and [`JwtHeader`] (parsed JWT header).
 
+use crate::config::TokenValidation;
 use crate::exceptions::JwtError;
-use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
-use serde::{Deserialize, Serialize};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use uuid::Uuid;
 
 /// Decoded JWT claims from a USSO token.
@@ -13,8 +14,25 @@ use uuid::Uuid;
 /// All fields are `Option`al since claims may vary by token type and issuer.
 /// Use the [`uid`](Self::uid) helper to extract a UUID from either `user_id`
 /// or `sub`, supporting both standard UUID and base64-encoded formats.
+///
+/// Service-specific claims can be deserialized alongside the standard ones
+/// through the `Extra` type parameter, which is flattened into the payload:
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use usso::schemas::UserData;
+///
+/// #[derive(Deserialize, Debug, Clone)]
+/// struct BillingClaims {
+///     plan: String,
+/// }
+///
+/// let claims: UserData<BillingClaims> =
+///     serde_json::from_str(r#"{"sub": "u_1", "plan": "pro"}"#).unwrap();
+/// assert_eq!(claims.extra.plan, "pro");
+/// ```
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UserData {
+pub struct UserData<Extra = ()> {
     pub iss: Option<String>,
     pub sub: Option<String>,
     pub aud: Option<String>,
@@ -38,11 +56,15 @@ pub struct UserData {
     pub acr: Option<String>,
     pub amr: Option<Vec<String>>,
     pub signing_level: Option<String>,
+    /// When the user authenticated (seconds since the epoch).
+    pub auth_time: Option<i64>,
     pub data: Option<serde_json::Value>,
     pub token: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-impl UserData {
+impl<Extra> UserData<Extra> {
     /// Extract a [`Uuid`] from either `user_id` or `sub`.
     ///
     /// Supports standard UUID strings and base64-encoded UUIDs (optionally
@@ -66,59 +88,94 @@ impl UserData {
 pub struct JWTConfig {
     pub jwk_url: Option<String>,
     pub keys: Option<Jwks>,
+    /// The only accepted `alg` when [`TokenValidation::algorithms`] is
+    /// `None`; empty (the default) accepts every supported algorithm.
+    #[deprecated(note = "set `validation.algorithms`, which takes precedence, instead")]
+    #[serde(default)]
     pub algorithm: String,
     pub header: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub validation: TokenValidation,
 }
 
 impl JWTConfig {
     /// Create a new `JWTConfig` with the given JWKS URL and/or inline keys.
+    #[allow(deprecated)]
     pub fn new(jwk_url: Option<String>, keys: Option<Jwks>) -> Self {
         JWTConfig {
             jwk_url,
             keys,
-            algorithm: "RS256".to_string(),
+            algorithm: String::new(),
             header: std::collections::HashMap::new(),
+            validation: TokenValidation::default(),
         }
     }
 
     /// Decode and validate a JWT using this configuration's key set.
     ///
-    /// Looks up the key by the `kid` header claim. Auto-detects the algorithm
-    /// from the JWT header (RS256, ES256, ES384, ES512, EdDSA, etc.).
+    /// Looks up the key by the `kid` header claim. Inline [`keys`](Self::keys)
+    /// are searched first; otherwise (or if the `kid` is not among them) the
+    /// key set for [`jwk_url`](Self::jwk_url) is read from the
+    /// [global JWKS cache](crate::jwks::global_cache), refetched (blocking)
+    /// once it expires or, rate limited, when the `kid` is unknown.
+    /// Auto-detects the algorithm from the JWT header (RS256, ES256, ES384,
+    /// ES512, EdDSA, etc.) and checks the claims against
+    /// [`validation`](Self::validation). HMAC-signed tokens are rejected: use
+    /// [`UssoAuth`](crate::core::UssoAuth) with a shared secret for those.
     pub fn decode(&self, token: &str) -> Result<UserData, crate::exceptions::USSOError> {
-        let header =
-            JwtHeader::from_token(token).map_err(|_| crate::exceptions::USSOError::InvalidToken)?;
-        match header.kid {
-            Some(kid) => {
-                if let Some(keyset) = &self.keys {
-                    let key = keyset.match_kid(kid.as_str());
-                    match key {
-                        Some(key) => crate::core::decode_token(key, token),
-                        None => Err(crate::exceptions::USSOError::InvalidToken),
-                    }
-                } else {
-                    Err(crate::exceptions::USSOError::Other(
-                        "keyset is not set".to_string(),
-                    ))
-                }
+        self.decode_claims(token)
+    }
+
+    /// Like [`decode`](Self::decode), but deserializes the verified payload
+    /// into a caller-provided claims type `C` (for example `UserData<MyExtra>`).
+    pub fn decode_claims<C: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<C, crate::exceptions::USSOError> {
+        #[allow(deprecated)]
+        let validation = self.validation.or_algorithm(&self.algorithm);
+        let header = JwtHeader::from_token(token)?;
+        if header.alg.starts_with("HS") {
+            return Err(crate::exceptions::USSOError::AlgorithmNotAllowed(header.alg));
+        }
+        let kid = header.kid.ok_or_else(|| {
+            crate::exceptions::USSOError::MalformedToken("header has no kid".into())
+        })?;
+        if let Some(key) = self.keys.as_ref().and_then(|keyset| keyset.match_kid(&kid)) {
+            return crate::core::decode_claims(key, token, &validation);
+        }
+        match &self.jwk_url {
+            Some(url) => {
+                let key = crate::jwks::global_cache().find_key_sync(url, &kid)?;
+                crate::core::decode_claims(&key, token, &validation)
             }
-            None => Err(crate::exceptions::USSOError::InvalidToken),
+            None if self.keys.is_some() => Err(crate::exceptions::USSOError::UnknownKey(kid)),
+            None => Err(crate::exceptions::USSOError::Other(
+                "keyset is not set".to_string(),
+            )),
         }
     }
 }
 
-/// A single JSON Web Key (JWK) supporting RSA and EC public keys.
+/// A single JSON Web Key (JWK) supporting RSA, EC, OKP and symmetric keys.
 ///
 /// RSA keys use `n` (modulus) and `e` (exponent).
 /// EC keys use `crv` (curve name), `x` and `y` (coordinates).
-/// The [`kty`](Self::kty) field distinguishes the key type (`"RSA"` or `"EC"`).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// OKP keys use `crv` (`Ed25519` or `Ed448`) and `x`.
+/// Symmetric (HMAC) keys use `k`.
+/// The [`kty`](Self::kty) field distinguishes the key type (`"RSA"`, `"EC"`,
+/// `"OKP"` or `"oct"`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Jwk {
+    /// Key ID; empty for keys published without one (e.g. in DPoP proofs).
+    #[serde(default)]
     pub kid: String,
     pub kty: String,
     #[serde(default)]
     pub alg: Option<String>,
-    pub r#use: String,
+    /// Intended use (`sig` or `enc`); optional in real-world key sets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#use: Option<String>,
     #[serde(default)]
     pub n: Option<String>,
     #[serde(default)]
@@ -129,6 +186,34 @@ pub struct Jwk {
     pub x: Option<String>,
     #[serde(default)]
     pub y: Option<String>,
+    /// Base64url secret of a symmetric (`oct`) key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k: Option<String>,
+    /// X.509 certificate chain (standard base64 DER), leaf first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x5c: Option<Vec<String>>,
+    /// Base64url SHA-1 thumbprint of the leaf certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x5t: Option<String>,
+    /// Base64url SHA-256 thumbprint of the leaf certificate.
+    #[serde(default, rename = "x5t#S256", skip_serializing_if = "Option::is_none")]
+    pub x5t_s256: Option<String>,
+    /// URL of the certificate chain; informational, never fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x5u: Option<String>,
+}
+
+impl Jwk {
+    /// A symmetric signing key for HS256/HS384/HS512 tokens.
+    pub fn from_secret(kid: impl Into<String>, secret: &[u8]) -> Self {
+        Jwk {
+            kid: kid.into(),
+            kty: "oct".into(),
+            r#use: Some("sig".into()),
+            k: Some(URL_SAFE_NO_PAD.encode(secret)),
+            ..Default::default()
+        }
+    }
 }
 
 /// A JSON Web Key Set (JWKS) containing multiple public keys.
@@ -164,7 +249,7 @@ impl JwtHeader {
             return Err(JwtError::InvalidFormat);
         }
         let header_base64 = parts[0];
-        match STANDARD_NO_PAD.decode(header_base64) {
+        match URL_SAFE_NO_PAD.decode(header_base64) {
             Ok(header_bytes) => match serde_json::from_slice(&header_bytes) {
                 Ok(header_str) => Ok(header_str),
                 Err(_) => Err(JwtError::DecodingError("Invalid UTF-8 in header".to_string())),
@@ -0,0 +1,149 @@
+//! Step-up authentication requirements (RFC 9470).
+//!
+//! A valid token proves who the user is, but not how strongly or how
+//! recently they authenticated. A [`StepUpRequirement`] checks the `acr`,
+//! `amr`, `signing_level` and `auth_time` (or `iat`) claims of a verified
+//! token before a sensitive operation. Tokens that fall short fail with
+//! [`USSOError::StepUpRequired`], whose [`StepUpChallenge`] renders the RFC 9470
+//! `WWW-Authenticate` challenge telling the client to re-authenticate.
+//!
+//! Levels (`acr`, `signing_level`) are compared by their position in
+//! [`acr_levels`](StepUpRequirement::acr_levels) when it is set, numerically
+//! when both values are numbers, and for equality otherwise.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use usso::core::UssoAuth;
+//! use usso::step_up::StepUpRequirement;
+//!
+//! let auth = UssoAuth::new(None, Some("https://sso.usso.io".into()));
+//! let requirement = StepUpRequirement {
+//!     required_amr: vec!["otp".into()],
+//!     max_age: Some(Duration::from_secs(300)),
+//!     ..Default::default()
+//! };
+//!
+//! match auth.user_data_from_token_with_step_up("eyJ...", Some("access"), &requirement) {
+//!     Ok(user) => println!("approved for {:?}", user.sub),
+//!     Err(err) => println!("{err}"), // e.g. USSOError::StepUpRequired
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::exceptions::{StepUpChallenge, USSOError};
+use crate::schemas::UserData;
+
+/// How strongly and how recently the user must have authenticated.
+///
+/// Every check is optional; the default requirement accepts every token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StepUpRequirement {
+    /// Minimum `acr` (authentication context class) of the token.
+    pub min_acr: Option<String>,
+    /// Authentication methods that must all appear in the token's `amr`.
+    pub required_amr: Vec<String>,
+    /// Minimum `signing_level` of the token.
+    pub min_signing_level: Option<String>,
+    /// Maximum time since the user authenticated, from `auth_time` (or `iat`
+    /// when the token has no `auth_time`).
+    pub max_age: Option<Duration>,
+    /// Known levels from weakest to strongest, used to order `acr` and
+    /// `signing_level` values that are not numbers.
+    pub acr_levels: Option<Vec<String>>,
+}
+
+impl StepUpRequirement {
+    /// Check the claims of a verified token.
+    pub fn check<E>(&self, user: &UserData<E>) -> Result<(), USSOError> {
+        self.check_values(
+            user.acr.as_deref(),
+            user.amr.as_deref().unwrap_or_default(),
+            user.signing_level.as_deref(),
+            user.auth_time.or(user.iat),
+        )
+    }
+
+    /// Check the raw claims of a verified token, for custom claims types.
+    pub fn check_claims(&self, claims: &Value) -> Result<(), USSOError> {
+        let text = |name: &str| claims.get(name).and_then(Value::as_str);
+        let amr: Vec<String> = claims
+            .get("amr")
+            .and_then(Value::as_array)
+            .map(|methods| {
+                methods
+                    .iter()
+                    .filter_map(|method| method.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let auth_time = ["auth_time", "iat"]
+            .iter()
+            .find_map(|name| claims.get(*name).and_then(Value::as_i64));
+        self.check_values(text("acr"), &amr, text("signing_level"), auth_time)
+    }
+
+    fn check_values(
+        &self,
+        acr: Option<&str>,
+        amr: &[String],
+        signing_level: Option<&str>,
+        auth_time: Option<i64>,
+    ) -> Result<(), USSOError> {
+        if let Some(min_acr) = &self.min_acr {
+            if !acr.is_some_and(|acr| self.level_at_least(acr, min_acr)) {
+                return Err(self.challenge(format!("acr {min_acr} or higher is required")));
+            }
+        }
+        if let Some(missing) = self.required_amr.iter().find(|method| !amr.contains(method)) {
+            return Err(self.challenge(format!("authentication with {missing} is required")));
+        }
+        if let Some(min_level) = &self.min_signing_level {
+            if !signing_level.is_some_and(|level| self.level_at_least(level, min_level)) {
+                return Err(self.challenge(format!(
+                    "signing level {min_level} or higher is required"
+                )));
+            }
+        }
+        if let Some(max_age) = self.max_age {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            let max_age = i64::try_from(max_age.as_secs()).unwrap_or(i64::MAX);
+            if auth_time.is_none_or(|at| now.saturating_sub(at) > max_age) {
+                return Err(self.challenge("a more recent authentication is required".into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `level` is at least as strong as `minimum`.
+    fn level_at_least(&self, level: &str, minimum: &str) -> bool {
+        if let Some(levels) = &self.acr_levels {
+            let rank = |value: &str| levels.iter().position(|known| known == value);
+            return match (rank(level), rank(minimum)) {
+                (Some(level), Some(minimum)) => level >= minimum,
+                _ => false,
+            };
+        }
+        match (level.parse::<f64>(), minimum.parse::<f64>()) {
+            (Ok(level), Ok(minimum)) => level >= minimum,
+            _ => level == minimum,
+        }
+    }
+
+    fn challenge(&self, reason: String) -> USSOError {
+        USSOError::StepUpRequired(StepUpChallenge {
+            reason,
+            acr_values: self.min_acr.clone(),
+            max_age: self.max_age.map(|max_age| max_age.as_secs()),
+        })
+    }
+}
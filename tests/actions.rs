@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use usso::authorization::{
+    check_access, is_authorized, AccessDecision, AccessMismatch, Action, ActionRegistry, Scope,
+    ScopeSet,
+};
+use usso::exceptions::ScopeParseError;
+
+const APPROVE: &str = "approve";
+const EXPORT: &str = "export";
+
+/// `approve` is implied by `manage` (and what is above it), `export` implies
+/// `read`, and unknown verbs grant nothing.
+fn registry() -> Arc<ActionRegistry> {
+    let mut registry = ActionRegistry::default();
+    registry
+        .implies
+        .insert("manage".into(), vec!["approve".into()]);
+    registry
+        .implies
+        .insert("export".into(), vec!["read".into()]);
+    registry.unknown_action = None;
+    Arc::new(registry)
+}
+
+fn strings(scopes: &[&str]) -> Vec<String> {
+    scopes.iter().map(|scope| scope.to_string()).collect()
+}
+
+#[test]
+fn custom_verbs_follow_the_registry() {
+    let registry = registry();
+    assert!(registry.is_known("approve"));
+
+    assert!(registry.is_known("read"));
+    assert!(!registry.is_known("publish"));
+    assert_eq!(registry.level("manage"), Some(50));
+    assert_eq!(registry.level(APPROVE), None);
+    // `Action` only names the built-in actions.
+    assert_eq!("approve".parse::<Action>(), Err(()));
+
+    assert_eq!(
+        Scope::parse_with("export", &registry).unwrap_err(),
+        ScopeParseError::MissingAction("export".into())
+    );
+    assert_eq!(
+        Scope::parse_with("publish:reports", &registry).unwrap_err(),
+        ScopeParseError::UnknownAction("publish".into())
+    );
+    let scope = Scope::parse_with("approve:billing/invoices", &registry).unwrap();
+    assert_eq!(scope.action(), APPROVE);
+    assert_eq!(scope.to_string(), "approve:billing/invoices");
+    assert!("approve:billing/invoices".parse::<Scope>().is_err());
+    let scope = Scope::parse_with("*:billing", &registry).unwrap();
+    assert_eq!(scope.action(), "owner");
+
+    for (granted, requested, expected) in [
+        ("manage", APPROVE, true),
+        ("superadmin", APPROVE, true),
+        ("delete", APPROVE, false),
+        (APPROVE, "read", false),
+        (APPROVE, APPROVE, true),
+        (EXPORT, "read", true),
+        (EXPORT, "create", false),
+        ("admin", EXPORT, false),
+    ] {
+        assert_eq!(
+            registry.implies(granted, requested),
+            expected,
+            "{granted} {requested}"
+        );
+    }
+    // Without an `unknown_action`, unknown verbs grant nothing.
+    assert!(!registry.is_authorized("publish:reports", "reports", Some("read"), None, false));
+    // The default registry still counts them as `read`.
+    assert!(is_authorized(
+        "publish:reports",
+        "reports",
+        Some(Action::Read),
+        None,
+        false
+    ));
+}
+
+#[test]
+fn authorization_methods_use_the_registry() {
+    let registry = registry();
+    let scopes = strings(&[
+        "delete:billing/*",
+        "manage:orders/*",
+        "export:reports?tenant_id=t1",
+        "!approve:orders/refunds",
+    ]);
+    let set = ScopeSet::compile_with(&scopes, &registry).unwrap();
+    assert!(ScopeSet::compile(&scopes).is_err());
+
+    let cases = [
+        ("billing/invoices", APPROVE, false),
+        ("billing/invoices", "delete", true),
+        ("orders/returns", APPROVE, true),
+        ("orders/refunds", APPROVE, false),
+        ("orders/refunds", "manage", false),
+        ("reports", "read", true),
+        ("reports", EXPORT, true),
+        ("reports", "update", false),
+    ];
+    for (path, action, expected) in cases {
+        assert_eq!(
+            registry.check_access(&scopes, path, Some(action), None, false),
+            expected,
+            "{path} {action}"
+        );
+        assert_eq!(set.check_access(path, Some(action), None, false), expected);
+        assert_eq!(
+            registry.check_access_scopes(set.scopes(), path, Some(action), None, false),
+            expected
+        );
+        assert_eq!(
+            set.get_scope_filters(action, path),
+            registry.get_scope_filters(action, path, &scopes)
+        );
+    }
+    // The same scopes checked with the default registry, which counts the
+    // unknown `approve` as `read`.
+    assert!(ActionRegistry::default().check_access(
+        &scopes,
+        "billing/invoices",
+        Some(APPROVE),
+        None,
+        false
+    ));
+    // ... so the `approve` deny takes away reading.
+    assert!(!check_access(
+        &scopes,
+        "orders/refunds",
+        Some(Action::Read),
+        None,
+        false
+    ));
+
+    let decision = registry.explain_access(&scopes, "billing/invoices", Some(APPROVE), None, false);
+    assert_eq!(decision.action.as_deref(), Some(APPROVE));
+    assert_eq!(decision.rejected[0].mismatches, [AccessMismatch::Level]);
+    assert_eq!(
+        set.explain_access("billing/invoices", Some(APPROVE), None, false),
+        decision
+    );
+    // Decisions on custom verbs survive a serialization round trip.
+    let json = serde_json::to_string(&decision).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AccessDecision>(&json).unwrap(),
+        decision
+    );
+    assert_eq!(
+        registry.get_scope_filters(EXPORT, "reports", &scopes),
+        [HashMap::from([("tenant_id".to_string(), "t1".to_string())])]
+    );
+
+    for (subset, expected) in [
+        ("approve:orders/*", false),
+        ("approve:orders/returns", true),
+        ("read:reports?tenant_id=t1", true),
+        ("approve:billing/invoices", false),
+    ] {
+        assert_eq!(
+            registry.has_subset_scope(subset, &scopes),
+            expected,
+            "{subset}"
+        );
+        let subset = Scope::parse_with(subset, &registry).unwrap();
+        assert_eq!(set.has_subset_scope(&subset), expected);
+    }
+    assert!(registry.is_subset_scope("approve:orders", "admin:orders"));
+    assert!(!registry.is_subset_scope("approve:orders", "delete:orders"));
+    assert_eq!(
+        registry.get_common_scopes(&strings(&["approve:orders"]), &strings(&["delete:orders"])),
+        Vec::<String>::new()
+    );
+
+    let filter = HashMap::from([("user_id".to_string(), "u1".to_string())]);
+    let owner = |self_action, action| {
+        registry.owner_authorization(Some(&filter), Some("u1"), self_action, action, None, None)
+    };
+    assert!(owner(Some("owner"), Some(APPROVE)));
+    assert!(!owner(Some("delete"), Some(APPROVE)));
+}
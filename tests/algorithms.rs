@@ -0,0 +1,239 @@
+mod common;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde_json::json;
+use usso::config::{AuthConfig, HmacSecret};
+use usso::core::{decode_token, UssoAuth};
+use usso::exceptions::USSOError;
+use usso::jwks::local::jwk_from_pem;
+use usso::schemas::{JWTConfig, Jwk, Jwks};
+
+#[test]
+fn verifies_es256k_tokens() {
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
+
+    let signing_key = SigningKey::from_slice(&[5u8; 32]).unwrap();
+    let point = signing_key.verifying_key().to_encoded_point(false);
+    let jwk: Jwk = serde_json::from_value(json!({
+        "kid": "partner",
+        "kty": "EC",
+        "crv": "secp256k1",
+        "alg": "ES256K",
+        "x": URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+        "y": URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+    }))
+    .unwrap();
+
+    let header = json!({ "alg": "ES256K", "typ": "JWT", "kid": "partner" });
+    let token = common::sign_with(&header, &common::access_claims(), |input| {
+        let signature: Signature = signing_key.sign(input);
+        signature.to_bytes().to_vec()
+    });
+    let user = decode_token(&jwk, &token).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+
+    let mut tampered = common::access_claims();
+    tampered["sub"] = "u_2".into();
+    let parts: Vec<&str> = token.split('.').collect();
+    let forged = format!(
+        "{}.{}.{}",
+        parts[0],
+        URL_SAFE_NO_PAD.encode(tampered.to_string()),
+        parts[2]
+    );
+    let err = decode_token(&jwk, &forged).unwrap_err();
+    assert!(matches!(err, USSOError::InvalidSignature), "{err:?}");
+}
+
+#[test]
+fn verifies_ed448_tokens_from_a_pem_key() {
+    use ed448_goldilocks_plus::pkcs8::{EncodePublicKey, LineEnding};
+    use ed448_goldilocks_plus::SigningKey;
+
+    let signing_key = SigningKey::try_from(&[9u8; 57][..]).unwrap();
+    let pem = signing_key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    let jwk = jwk_from_pem(&pem, "ed448").unwrap();
+    assert_eq!(
+        (jwk.kty.as_str(), jwk.crv.as_deref(), jwk.alg.as_deref()),
+        ("OKP", Some("Ed448"), Some("EdDSA"))
+    );
+
+    let header = json!({ "alg": "EdDSA", "typ": "JWT", "kid": "ed448" });
+    let token = common::sign_with(&header, &common::access_claims(), |input| {
+        signing_key.sign_raw(input).to_bytes().to_vec()
+    });
+    let auth = UssoAuth::new(Some(AuthConfig::from_keys(Jwks { keys: vec![jwk] })), None);
+    let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+
+    // An Ed25519 signature does not verify against the Ed448 key.
+    let token = common::sign_with_header(json!({ "kid": "ed448" }), &common::access_claims());
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::InvalidSignature), "{err:?}");
+}
+
+#[test]
+fn verifies_hmac_tokens_with_a_shared_secret() {
+    let config = AuthConfig {
+        hmac_secret: Some(HmacSecret::new("internal-shared-secret")),
+        keys: Some(serde_json::from_str(&common::jwks_json()).unwrap()),
+        ..Default::default()
+    };
+    let auth = UssoAuth::new(Some(config), None);
+    assert_eq!(
+        format!("{:?}", HmacSecret::new("internal-shared-secret")),
+        "HmacSecret(..)"
+    );
+
+    // Legacy internal tokens carry no `kid`.
+    for alg in [Algorithm::HS256, Algorithm::HS384, Algorithm::HS512] {
+        let token = encode(
+            &Header::new(alg),
+            &common::access_claims(),
+            &EncodingKey::from_secret(b"internal-shared-secret"),
+        )
+        .unwrap();
+        let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+        assert_eq!(user.sub.as_deref(), Some("u_1"), "{alg:?}");
+    }
+
+    let token = encode(
+        &Header::new(Algorithm::HS256),
+        &common::access_claims(),
+        &EncodingKey::from_secret(b"wrong-secret"),
+    )
+    .unwrap();
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::InvalidSignature), "{err:?}");
+
+    // A symmetric JWK works with the plain decoding functions too.
+    let jwk = Jwk::from_secret("internal", b"internal-shared-secret");
+    let mut header = Header::new(Algorithm::HS384);
+    header.kid = Some("internal".into());
+    let token = encode(
+        &header,
+        &common::access_claims(),
+        &EncodingKey::from_secret(b"internal-shared-secret"),
+    )
+    .unwrap();
+    assert!(decode_token(&jwk, &token).is_ok());
+}
+
+#[test]
+fn rejects_hmac_tokens_keyed_with_a_public_key() {
+    // Algorithm confusion: an HS256 token "signed" with the public key bytes.
+    let public_key = common::signing_key().verifying_key().to_bytes();
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some(common::TEST_KID.into());
+    let token = encode(
+        &header,
+        &common::access_claims(),
+        &EncodingKey::from_secret(&public_key),
+    )
+    .unwrap();
+
+    let keys = serde_json::from_str(&common::jwks_json()).unwrap();
+    let auth = UssoAuth::new(Some(AuthConfig::from_keys(keys)), None);
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::AlgorithmNotAllowed(_)), "{err:?}");
+}
+
+#[test]
+fn ignores_symmetric_keys_from_key_sets() {
+    let secret = b"published-secret";
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some("shared".into());
+    let token = encode(
+        &header,
+        &common::access_claims(),
+        &EncodingKey::from_secret(secret),
+    )
+    .unwrap();
+    let oct = Jwk::from_secret("shared", secret);
+
+    let inline = AuthConfig::from_keys(Jwks {
+        keys: vec![oct.clone()],
+    });
+    let err = UssoAuth::new(Some(inline), None)
+        .user_data_from_token(&token, None)
+        .unwrap_err();
+    assert!(matches!(err, USSOError::AlgorithmNotAllowed(_)), "{err:?}");
+
+    let mut server = mockito::Server::new();
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    let body = json!({ "keys": [common::jwk(), oct] }).to_string();
+    let _mock = server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url.clone()))), None);
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::AlgorithmNotAllowed(_)), "{err:?}");
+    let keys = usso::jwks::fetch_jwks_sync(&jwk_url).unwrap();
+    assert_eq!(keys.keys.len(), 1);
+    assert_eq!(keys.keys[0].kid, common::TEST_KID);
+
+    let config = usso::schemas::JWTConfig::new(Some(jwk_url), None);
+    let err = config.decode(&token).unwrap_err();
+    assert!(matches!(err, USSOError::AlgorithmNotAllowed(_)), "{err:?}");
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_algorithm_field_restricts_algorithms() {
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::{Signature, SigningKey};
+
+    let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+    let point = signing_key.verifying_key().to_encoded_point(false);
+    let keys: Jwks = serde_json::from_value(json!({ "keys": [{
+        "kid": "p256",
+        "kty": "EC",
+        "crv": "P-256",
+        "x": URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+        "y": URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+    }] }))
+    .unwrap();
+    let header = json!({ "alg": "ES256", "typ": "JWT", "kid": "p256" });
+    let token = common::sign_with(&header, &common::access_claims(), |input| {
+        let signature: Signature = signing_key.sign(input);
+        signature.to_bytes().to_vec()
+    });
+
+    let auth = |algorithm: &str, algorithms: Option<Vec<String>>| {
+        let mut config = AuthConfig::from_keys(keys.clone());
+        config.algorithm = algorithm.into();
+        config.validation.algorithms = algorithms;
+        UssoAuth::new(Some(config), None).user_data_from_token(&token, None)
+    };
+    assert!(auth("", None).is_ok());
+    assert!(auth("ES256", None).is_ok());
+    let err = auth("RS256", None).unwrap_err();
+    assert!(
+        matches!(&err, USSOError::AlgorithmNotAllowed(alg) if alg == "ES256"),
+        "{err:?}"
+    );
+    // An explicit allow-list takes precedence.
+    assert!(auth("RS256", Some(vec!["ES256".into()])).is_ok());
+
+    let mut config = JWTConfig::new(None, Some(keys.clone()));
+    assert!(config.decode(&token).is_ok());
+    config.algorithm = "RS256".into();
+    assert!(matches!(
+        config.decode(&token),
+        Err(USSOError::AlgorithmNotAllowed(_))
+    ));
+
+    // Configs deserialized without the field accept every algorithm.
+    let config: JWTConfig =
+        serde_json::from_value(json!({ "jwk_url": null, "keys": keys, "header": {} })).unwrap();
+    assert!(config.decode(&token).is_ok());
+}
@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+
+use usso::authorization::{
+    check_access, check_access_scopes, explain_access, get_common_scopes, get_scope_filters,
+    has_subset_scope, is_authorized, is_subset_scope, AccessMismatch, Action, Scope, ScopeSet,
+};
+use usso::exceptions::ScopeParseError;
+
+fn scopes(scopes: &[&str]) -> Vec<Scope> {
+    scopes.iter().map(|scope| scope.parse().unwrap()).collect()
+}
+
+#[test]
+fn parses_and_displays_scopes() {
+    let scope: Scope = "read:billing/invoices?workspace_id=w1&tenant_id=t1"
+        .parse()
+        .unwrap();
+    assert_eq!(scope.action(), "read");
+    assert_eq!(scope.path(), ["billing", "invoices"]);
+    assert_eq!(scope.filters()["tenant_id"], "t1");
+    // Canonical form: filters sorted by key, `*` spelled as `owner`.
+    assert_eq!(
+        scope.to_string(),
+        "read:billing/invoices?tenant_id=t1&workspace_id=w1"
+    );
+    assert_eq!(
+        "*:users/*".parse::<Scope>().unwrap().to_string(),
+        "owner:users/*"
+    );
+
+    let json = serde_json::to_string(&scope).unwrap();
+    assert_eq!(
+        json,
+        "\"read:billing/invoices?tenant_id=t1&workspace_id=w1\""
+    );
+    assert_eq!(serde_json::from_str::<Scope>(&json).unwrap(), scope);
+    assert!(serde_json::from_str::<Scope>("\"approve:billing\"").is_err());
+}
+
+#[test]
+fn rejects_malformed_scopes() {
+    let cases = [
+        ("billing", ScopeParseError::MissingAction("billing".into())),
+        (
+            ":billing",
+            ScopeParseError::MissingAction(":billing".into()),
+        ),
+        (
+            "approve:billing",
+            ScopeParseError::UnknownAction("approve".into()),
+        ),
+        (
+            "read:billing//invoices",
+            ScopeParseError::EmptySegment("read:billing//invoices".into()),
+        ),
+        ("read:", ScopeParseError::EmptySegment("read:".into())),
+        (
+            "read:billing?tenant_id",
+            ScopeParseError::InvalidFilter("tenant_id".into()),
+        ),
+        (
+            "read:billing?tenant_id=",
+            ScopeParseError::InvalidFilter("tenant_id=".into()),
+        ),
+        (
+            "read:billing?a=1&&b=2",
+            ScopeParseError::InvalidFilter("".into()),
+        ),
+        (
+            "read:billing?a=1&a=2",
+            ScopeParseError::InvalidFilter("duplicate key a".into()),
+        ),
+    ];
+    for (scope, expected) in cases {
+        assert_eq!(scope.parse::<Scope>().unwrap_err(), expected, "{scope}");
+    }
+}
+
+#[test]
+fn parsed_scopes_agree_with_string_checks() {
+    let raw = [
+        "read:users",
+        "admin:billing/*?tenant_id=t1",
+        "update:*/reports",
+    ];
+    let strings: Vec<String> = raw.iter().map(|scope| scope.to_string()).collect();
+    let parsed = scopes(&raw);
+    let tenant = HashMap::from([("tenant_id".to_string(), "t1".to_string())]);
+    let other_tenant = HashMap::from([("tenant_id".to_string(), "t2".to_string())]);
+
+    let requests = [
+        ("users", Some(Action::Read), None),
+        ("users", Some(Action::Delete), None),
+        ("billing/invoices", Some(Action::Delete), Some(&tenant)),
+        (
+            "billing/invoices",
+            Some(Action::Delete),
+            Some(&other_tenant),
+        ),
+        ("sales/reports", Some(Action::Update), None),
+        ("sales/reports", Some(Action::Delete), None),
+        ("billing", None, None),
+    ];
+    for (path, action, filters) in requests {
+        assert_eq!(
+            check_access_scopes(&parsed, path, action, filters, false),
+            check_access(&strings, path, action, filters, false),
+            "{path} {action:?}"
+        );
+        for (scope, string) in parsed.iter().zip(&strings) {
+            assert_eq!(
+                scope.is_authorized(path, action, filters, false),
+                is_authorized(string, path, action, filters, false),
+                "{string} {path} {action:?}"
+            );
+        }
+    }
+
+    for (child, parent) in [
+        (
+            "read:billing/invoices?tenant_id=t1",
+            "admin:billing/*?tenant_id=t1",
+        ),
+        ("read:billing/invoices", "admin:billing/*?tenant_id=t1"),
+        ("admin:users", "read:users"),
+        ("read:users", "read:users"),
+    ] {
+        let (child_scope, parent_scope): (Scope, Scope) =
+            (child.parse().unwrap(), parent.parse().unwrap());
+        assert_eq!(
+            child_scope.is_subset_of(&parent_scope),
+            is_subset_scope(child, parent),
+            "{child} <= {parent}"
+        );
+    }
+}
+
+#[test]
+fn compiled_scope_sets_agree_with_string_checks() {
+    let strings: Vec<String> = [
+        "read:users",
+        "admin:billing/*?tenant_id=t1",
+        "update:*/reports",
+        "delete:sales/inv*/lines",
+        "read:archive/*/2024/reports",
+        "owner:*",
+        "manage:a/b/c/users",
+    ]
+    .iter()
+    .map(|scope| scope.to_string())
+    .collect();
+    let set = ScopeSet::compile(&strings).unwrap();
+    assert_eq!(set.len(), strings.len());
+
+    let tenant = HashMap::from([("tenant_id".to_string(), "t1".to_string())]);
+    let paths = [
+        "users",
+        "x/users",
+        "b/c/users",
+        "billing",
+        "billing/invoices",
+        "billing/invoices/lines",
+        "sales/reports",
+        "sales/invoices/lines",
+        "sales/orders/lines",
+        "archive/q1/2024/reports",
+        "2024/reports",
+        "*/reports",
+        "",
+    ];
+    let actions = [
+        None,
+        Some(Action::Read),
+        Some(Action::Update),
+        Some(Action::Delete),
+        Some(Action::Manage),
+        Some(Action::Superadmin),
+    ];
+    for path in paths {
+        for action in actions {
+            for filters in [None, Some(&tenant)] {
+                assert_eq!(
+                    set.check_access(path, action.map(Action::as_str), filters, false),
+                    check_access(&strings, path, action, filters, false),
+                    "{path} {action:?} {filters:?}"
+                );
+            }
+            if let Some(action) = action {
+                assert_eq!(
+                    set.get_scope_filters(action.as_str(), path),
+                    get_scope_filters(action, path, &strings),
+                    "{path} {action:?}"
+                );
+            }
+        }
+    }
+
+    let without_owner: Vec<String> = strings
+        .iter()
+        .filter(|s| *s != "owner:*")
+        .cloned()
+        .collect();
+    let set = ScopeSet::compile(&without_owner).unwrap();
+    for subset in [
+        "read:billing/invoices?tenant_id=t1",
+        "read:billing/invoices",
+        "update:sales/reports",
+        "delete:sales/reports",
+        "read:x/a/b/c/users",
+        "read:archive/*/2024/reports",
+        "manage:users",
+    ] {
+        assert_eq!(
+            set.has_subset_scope(&subset.parse().unwrap()),
+            has_subset_scope(subset, &without_owner),
+            "{subset}"
+        );
+    }
+
+    // Scopes that do not parse are skipped, not fatal.
+    let claimed = ["openid", "read:users", "profile", "bogus:users?x"];
+    let user: usso::schemas::UserData =
+        serde_json::from_value(serde_json::json!({ "scopes": claimed })).unwrap();
+    let set = ScopeSet::from_user_data(&user);
+    assert_eq!(set.len(), 1);
+    assert!(set.check_access("users", Some("read"), None, false));
+    assert!(ScopeSet::compile(&user.scopes.clone().unwrap()).is_err());
+    let (lossy, errors) = ScopeSet::compile_lossy(&user.scopes.unwrap());
+    assert_eq!(lossy.scopes(), set.scopes());
+    assert_eq!(errors.len(), 3);
+}
+
+#[test]
+fn explains_access_decisions() {
+    let strings: Vec<String> = [
+        "read:billing/invoices?tenant_id=t1",
+        "admin:users",
+        "update:billing/*?tenant_id=t2",
+        "delete:billing/invoices?tenant_id=t1",
+    ]
+    .iter()
+    .map(|scope| scope.to_string())
+    .collect();
+    let tenant = HashMap::from([("tenant_id".to_string(), "t1".to_string())]);
+
+    let decision = explain_access(
+        &strings,
+        "billing/invoices",
+        Some(Action::Update),
+        Some(&tenant),
+        false,
+    );
+    assert!(decision.allowed);
+    assert_eq!(decision.considered, strings);
+    assert_eq!(
+        decision.granted_by.as_deref(),
+        Some("delete:billing/invoices?tenant_id=t1")
+    );
+    let rejected: Vec<_> = decision
+        .rejected
+        .iter()
+        .map(|rejected| (rejected.scope.as_str(), rejected.mismatches.clone()))
+        .collect();
+    assert_eq!(
+        rejected,
+        [
+            (strings[0].as_str(), vec![AccessMismatch::Level]),
+            (strings[1].as_str(), vec![AccessMismatch::Path]),
+            (strings[2].as_str(), vec![AccessMismatch::Filters]),
+        ]
+    );
+
+    let json = serde_json::to_value(&decision).unwrap();
+    assert_eq!(json["action"], "update");
+    assert_eq!(
+        json["rejected"][2]["mismatches"],
+        serde_json::json!(["filters"])
+    );
+    assert_eq!(
+        serde_json::from_value::<usso::authorization::AccessDecision>(json).unwrap(),
+        decision
+    );
+
+    let set = ScopeSet::compile(&strings).unwrap();
+    for (path, action) in [
+        ("billing/invoices", Some(Action::Superadmin)),
+        ("billing/reports", Some(Action::Read)),
+        ("users", Some(Action::Delete)),
+        ("users", None),
+    ] {
+        for filters in [None, Some(&tenant)] {
+            let decision = explain_access(&strings, path, action, filters, false);
+            assert_eq!(
+                decision.allowed,
+                check_access(&strings, path, action, filters, false),
+                "{path} {action:?}"
+            );
+            assert_eq!(
+                set.explain_access(path, action.map(Action::as_str), filters, false),
+                decision
+            );
+        }
+    }
+}
+
+#[test]
+fn deny_scopes_override_grants() {
+    let strings: Vec<String> = [
+        "admin:billing/*",
+        "deny:delete:billing/invoices",
+        "read:reports",
+        "!read:reports?tenant_id=t2",
+    ]
+    .iter()
+    .map(|scope| scope.to_string())
+    .collect();
+    let t1 = HashMap::from([("tenant_id".to_string(), "t1".to_string())]);
+    let t2 = HashMap::from([("tenant_id".to_string(), "t2".to_string())]);
+
+    let scope: Scope = "deny:delete:billing/invoices".parse().unwrap();
+    assert!(scope.is_deny());
+    assert_eq!(scope.to_string(), "!delete:billing/invoices");
+    assert!(!scope.is_authorized("billing/invoices", Some(Action::Read), None, false));
+    assert!(!is_authorized(
+        "!delete:billing/invoices",
+        "billing/invoices",
+        None,
+        None,
+        false
+    ));
+
+    let cases = [
+        ("billing/invoices", Some(Action::Update), None, true),
+        ("billing/invoices", Some(Action::Delete), None, false),
+        ("billing/invoices", Some(Action::Admin), None, false),
+        ("billing/payments", Some(Action::Delete), None, true),
+        ("billing/invoices", None, None, true),
+        ("reports", Some(Action::Read), Some(&t1), true),
+        ("reports", Some(Action::Read), Some(&t2), false),
+        // A deny with filters applies when the request has none.
+        ("reports", Some(Action::Read), None, false),
+        ("reports", None, None, false),
+    ];
+    let set = ScopeSet::compile(&strings).unwrap();
+    let parsed = scopes(&strings.iter().map(String::as_str).collect::<Vec<_>>());
+    for (path, action, filters, expected) in cases {
+        assert_eq!(
+            check_access(&strings, path, action, filters, false),
+            expected,
+            "{path} {action:?} {filters:?}"
+        );
+        assert_eq!(
+            set.check_access(path, action.map(Action::as_str), filters, false),
+            expected
+        );
+        assert_eq!(
+            check_access_scopes(&parsed, path, action, filters, false),
+            expected
+        );
+        let decision = explain_access(&strings, path, action, filters, false);
+        assert_eq!(decision.allowed, expected);
+        assert_eq!(
+            set.explain_access(path, action.map(Action::as_str), filters, false)
+                .allowed,
+            expected
+        );
+    }
+    let decision = explain_access(
+        &strings,
+        "billing/invoices",
+        Some(Action::Delete),
+        None,
+        false,
+    );
+    assert_eq!(decision.granted_by.as_deref(), Some("admin:billing/*"));
+    assert_eq!(
+        decision.denied_by.as_deref(),
+        Some("deny:delete:billing/invoices")
+    );
+
+    assert_eq!(
+        get_scope_filters(Action::Delete, "billing/invoices", &strings),
+        Vec::<HashMap<String, String>>::new()
+    );
+    assert_eq!(
+        get_scope_filters(Action::Update, "billing/invoices", &strings).len(),
+        1
+    );
+    // Filters cannot exclude t2, so the overlapping grant is dropped.
+    assert!(get_scope_filters(Action::Read, "reports", &strings).is_empty());
+    for (action, path) in [
+        (Action::Delete, "billing/invoices"),
+        (Action::Update, "billing/invoices"),
+        (Action::Read, "reports"),
+    ] {
+        assert_eq!(
+            set.get_scope_filters(action.as_str(), path),
+            get_scope_filters(action, path, &strings)
+        );
+    }
+
+    // Delegation never re-grants a denied capability.
+    for (subset, expected) in [
+        ("update:billing/invoices", true),
+        ("delete:billing/payments", true),
+        ("delete:billing/invoices", false),
+        ("admin:billing/*", false),
+        ("manage:billing/invoices", false),
+        ("read:reports?tenant_id=t1", true),
+        ("read:reports?tenant_id=t2", false),
+        ("read:reports", false),
+        ("!read:billing", true),
+    ] {
+        assert_eq!(has_subset_scope(subset, &strings), expected, "{subset}");
+        assert_eq!(
+            set.has_subset_scope(&subset.parse().unwrap()),
+            expected,
+            "{subset}"
+        );
+    }
+    assert!(!is_subset_scope(
+        "delete:billing/invoices",
+        "!delete:billing/invoices"
+    ));
+
+    let common = get_common_scopes(&["admin:billing/*".to_string()], &strings);
+    assert_eq!(
+        common,
+        [
+            "!read:reports?tenant_id=t2",
+            "admin:billing/*",
+            "deny:delete:billing/invoices"
+        ]
+    );
+    assert!(!check_access(
+        &common,
+        "billing/invoices",
+        Some(Action::Delete),
+        None,
+        false
+    ));
+}
@@ -0,0 +1,82 @@
+//! Helpers shared by the integration tests: an Ed25519 test key and a
+//! minimal JWS signer.
+
+#![allow(dead_code)]
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use usso::config::AuthConfig;
+use usso::core::UssoAuth;
+
+pub const TEST_KID: &str = "test-ed25519";
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+pub fn signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+/// The public JWK matching [`signing_key`].
+pub fn jwk() -> Value {
+    json!({
+        "kid": TEST_KID,
+        "kty": "OKP",
+        "alg": "EdDSA",
+        "use": "sig",
+        "crv": "Ed25519",
+        "x": URL_SAFE_NO_PAD.encode(signing_key().verifying_key().as_bytes()),
+    })
+}
+
+pub fn jwks_json() -> String {
+    json!({ "keys": [jwk()] }).to_string()
+}
+
+/// A `UssoAuth` trusting the inline test key set.
+pub fn auth() -> UssoAuth {
+    let keys = serde_json::from_str(&jwks_json()).unwrap();
+    UssoAuth::new(Some(AuthConfig::from_keys(keys)), None)
+}
+
+/// Sign `claims` as an EdDSA JWS with the given header fields merged in.
+pub fn sign_with_header(header: Value, claims: &Value) -> String {
+    let mut full_header = json!({ "alg": "EdDSA", "typ": "JWT", "kid": TEST_KID });
+    for (k, v) in header.as_object().unwrap() {
+        full_header[k] = v.clone();
+    }
+    sign_with(&full_header, claims, |input| {
+        signing_key().sign(input).to_bytes().to_vec()
+    })
+}
+
+/// Build a compact JWS from `header` and `claims`, signing with `signer`.
+pub fn sign_with(header: &Value, claims: &Value, signer: impl FnOnce(&[u8]) -> Vec<u8>) -> String {
+    let header_b64 = URL_SAFE_NO_PAD.encode(header.to_string());
+    let payload_b64 = URL_SAFE_NO_PAD.encode(claims.to_string());
+    let signing_input = format!("{}.{}", header_b64, payload_b64);
+    let signature = signer(signing_input.as_bytes());
+    format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// Sign `claims` as an EdDSA JWS using the test key.
+pub fn sign(claims: &Value) -> String {
+    sign_with_header(json!({}), claims)
+}
+
+/// Claims of a valid access token expiring in one hour.
+pub fn access_claims() -> Value {
+    json!({
+        "sub": "u_1",
+        "iss": "https://sso.usso.io",
+        "token_type": "access",
+        "iat": now(),
+        "exp": now() + 3600,
+    })
+}
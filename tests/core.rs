@@ -0,0 +1,135 @@
+mod common;
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use usso::config::AuthConfig;
+use usso::core::UssoAuth;
+use usso::schemas::UserData;
+
+#[derive(Deserialize, Debug, Clone)]
+struct BillingClaims {
+    plan: String,
+    seats: u32,
+}
+
+#[test]
+fn usso_auth_fetches_jwks_once() {
+    let mut server = mockito::Server::new();
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    let mock = server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .expect(1)
+        .create();
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url))), None);
+    let token = common::sign(&common::access_claims());
+    for _ in 0..3 {
+        let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+        assert_eq!(user.sub.as_deref(), Some("u_1"));
+    }
+    assert!(auth.user_data_from_token(&token, Some("refresh")).is_err());
+
+    mock.assert();
+}
+
+#[tokio::test]
+async fn usso_auth_validates_token_async() {
+    let mut server = mockito::Server::new_async().await;
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    let mock = server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .expect(1)
+        .create_async()
+        .await;
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url))), None);
+    let token = common::sign(&common::access_claims());
+    for _ in 0..2 {
+        let user = auth
+            .user_data_from_token_async(&token, Some("access"))
+            .await
+            .unwrap();
+        assert_eq!(user.sub.as_deref(), Some("u_1"));
+    }
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn usso_auth_verifies_api_key_async() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/sso/v1/apikeys/verify")
+        .match_body(mockito::Matcher::Json(serde_json::json!({"api_key": "sk-test"})))
+        .with_status(200)
+        .with_body(r#"{"sub": "u_api", "token_type": "access"}"#)
+        .create_async()
+        .await;
+
+    let auth = UssoAuth::new(None, Some(server.url()));
+    let user = auth
+        .user_data_from_api_key_async("sk-test", &HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_api"));
+
+    mock.assert_async().await;
+}
+
+#[test]
+fn usso_auth_decodes_custom_claims() {
+    let mut server = mockito::Server::new();
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .create();
+
+    let mut claims = common::access_claims();
+    claims["plan"] = "pro".into();
+    claims["seats"] = 5.into();
+    let token = common::sign(&claims);
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url))), None)
+        .with_claims::<UserData<BillingClaims>>();
+    let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+    assert_eq!(user.extra.plan, "pro");
+    assert_eq!(user.extra.seats, 5);
+    assert!(auth.user_data_from_token(&token, Some("refresh")).is_err());
+
+    // Tokens lacking the required extra claims are rejected.
+    let token = common::sign(&common::access_claims());
+    assert!(auth.user_data_from_token(&token, None).is_err());
+}
+
+#[test]
+fn decodes_headers_using_the_url_safe_alphabet() {
+    // This kid makes the base64url header contain `_`, which the standard
+    // alphabet rejects.
+    let kid = "k?";
+    let token =
+        common::sign_with_header(serde_json::json!({ "kid": kid }), &common::access_claims());
+    let header = token.split('.').next().unwrap();
+    assert!(header.contains(['-', '_']), "{header}");
+    assert_eq!(
+        usso::schemas::JwtHeader::from_token(&token)
+            .unwrap()
+            .kid
+            .as_deref(),
+        Some(kid)
+    );
+
+    let mut jwk = common::jwk();
+    jwk["kid"] = kid.into();
+    let keys = serde_json::from_value(serde_json::json!({ "keys": [jwk] })).unwrap();
+    let auth = UssoAuth::new(Some(AuthConfig::from_keys(keys)), None);
+    let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::collections::HashMap;
+
+use serde_json::json;
+use usso::core::UssoAuth;
+use usso::exceptions::{ClaimError, DiscoveryError, USSOError};
+
+fn serve_discovery(server: &mut mockito::Server, issuer: &str) {
+    let metadata = json!({
+        "issuer": issuer,
+        "jwks_uri": format!("{}/keys", server.url()),
+        "token_endpoint": format!("{}/token", server.url()),
+        "introspection_endpoint": format!("{}/introspect", server.url()),
+        "revocation_endpoint": format!("{}/revoke", server.url()),
+        "api_key_verify_endpoint": format!("{}/apikeys/verify", server.url()),
+        // Only about ID tokens: EdDSA access tokens are still accepted.
+        "id_token_signing_alg_values_supported": ["RS256"],
+    });
+    server
+        .mock("GET", "/.well-known/openid-configuration")
+        .with_status(200)
+        .with_body(metadata.to_string())
+        .create();
+    server
+        .mock("GET", "/keys")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .create();
+}
+
+#[test]
+fn configures_usso_auth_from_discovery() {
+    let mut server = mockito::Server::new();
+    let issuer = server.url();
+    serve_discovery(&mut server, &issuer);
+
+    let auth = UssoAuth::from_discovery_sync(&format!("{issuer}/")).unwrap();
+    let metadata = auth.provider_metadata().unwrap();
+    assert_eq!(metadata.jwks_uri, format!("{issuer}/keys"));
+    assert_eq!(
+        metadata.introspection_endpoint.as_deref(),
+        Some(format!("{issuer}/introspect").as_str())
+    );
+    let config = metadata.auth_config();
+    assert_eq!(config.validation.algorithms, None);
+    assert_eq!(
+        config.introspection.unwrap().config().endpoint,
+        format!("{issuer}/introspect")
+    );
+
+    let mut claims = common::access_claims();
+    claims["iss"] = issuer.clone().into();
+    let user = auth
+        .user_data_from_token(&common::sign(&claims), Some("access"))
+        .unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+
+    claims["iss"] = "https://evil.example".into();
+    let err = auth
+        .user_data_from_token(&common::sign(&claims), Some("access"))
+        .unwrap_err();
+    assert!(
+        matches!(err, USSOError::InvalidClaim(ClaimError::InvalidIssuer(_))),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn discovery_rejects_a_foreign_issuer() {
+    let mut server = mockito::Server::new_async().await;
+    serve_discovery(&mut server, "https://sso.usso.io");
+
+    let err = UssoAuth::from_discovery_async(&server.url())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, DiscoveryError::IssuerMismatch { ref found, .. } if found == "https://sso.usso.io"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn verifies_api_keys_at_the_discovered_endpoint() {
+    let mut server = mockito::Server::new();
+    let issuer = server.url();
+    serve_discovery(&mut server, &issuer);
+    let verify = server
+        .mock("POST", "/apikeys/verify")
+        .match_body(mockito::Matcher::Json(json!({"api_key": "sk-test"})))
+        .with_status(200)
+        .with_body(r#"{"sub": "u_api", "token_type": "access"}"#)
+        .create();
+
+    let auth = UssoAuth::from_discovery_sync(&issuer).unwrap();
+    let user = auth
+        .user_data_from_api_key("sk-test", &HashMap::new())
+        .unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_api"));
+
+    verify.assert();
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use usso::dpop::{jwk_thumbprint, DpopConfig};
+use usso::exceptions::USSOError;
+use usso::schemas::Jwk;
+
+const URL: &str = "https://api.example.com/orders";
+
+struct Client {
+    key: SigningKey,
+    jwk: Value,
+}
+
+impl Client {
+    fn new(seed: u8) -> Self {
+        let key = SigningKey::from_slice(&[seed; 32]).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let jwk = json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+            "y": URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+        });
+        Client { key, jwk }
+    }
+
+    fn jkt(&self) -> String {
+        let jwk: Jwk = serde_json::from_value(self.jwk.clone()).unwrap();
+        jwk_thumbprint(&jwk).unwrap()
+    }
+
+    /// An access token bound to this client's key.
+    fn token(&self) -> String {
+        let mut claims = common::access_claims();
+        claims["cnf"] = json!({ "jkt": self.jkt() });
+        common::sign(&claims)
+    }
+
+    fn proof_with(&self, header: Value, claims: Value) -> String {
+        let mut full_header = json!({ "typ": "dpop+jwt", "alg": "ES256", "jwk": self.jwk });
+        for (k, v) in header.as_object().unwrap() {
+            full_header[k] = v.clone();
+        }
+        common::sign_with(&full_header, &claims, |input| {
+            let signature: Signature = self.key.sign(input);
+            signature.to_bytes().to_vec()
+        })
+    }
+
+    fn proof(&self, jti: &str, method: &str, url: &str, token: &str) -> String {
+        self.proof_with(json!({}), proof_claims(jti, method, url, token))
+    }
+}
+
+fn proof_claims(jti: &str, method: &str, url: &str, token: &str) -> Value {
+    json!({
+        "jti": jti,
+        "htm": method,
+        "htu": url,
+        "iat": common::now(),
+        "ath": URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes())),
+    })
+}
+
+fn assert_invalid_proof(result: Result<impl std::fmt::Debug, USSOError>) {
+    let err = result.unwrap_err();
+    assert!(matches!(err, USSOError::InvalidDpopProof(_)), "{err:?}");
+    assert_eq!(err.error_code(), "invalid_dpop_proof");
+}
+
+#[test]
+fn thumbprint_matches_rfc_7638_example() {
+    let jwk: Jwk = serde_json::from_value(json!({
+        "kty": "RSA",
+        "e": "AQAB",
+        "alg": "RS256",
+        "kid": "2011-04-29",
+        "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+    }))
+    .unwrap();
+    assert_eq!(
+        jwk_thumbprint(&jwk).unwrap(),
+        "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+    );
+}
+
+#[test]
+fn verifies_proofs_bound_to_the_token() {
+    let auth = common::auth();
+    let client = Client::new(3);
+    let token = client.token();
+
+    let proof = client.proof("p1", "POST", URL, &token);
+    let user = auth
+        .user_data_from_dpop_token(
+            &token,
+            &proof,
+            "POST",
+            &format!("{URL}?page=2"),
+            Some("access"),
+        )
+        .unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+    // Each proof is accepted once.
+    assert_invalid_proof(auth.user_data_from_dpop_token(&token, &proof, "POST", URL, None));
+    // A bound token is not a bearer token.
+    assert_invalid_proof(auth.user_data_from_token(&token, None));
+
+    let other = Client::new(4);
+    let other_token = other.token();
+    let bearer = common::sign(&common::access_claims());
+    let stale = {
+        let mut claims = proof_claims("p6", "POST", URL, &token);
+        claims["iat"] = (common::now() - 600).into();
+        client.proof_with(json!({}), claims)
+    };
+    let rejected = [
+        (client.proof("p2", "GET", URL, &token), &token),
+        (
+            client.proof("p3", "POST", "https://api.example.com/users", &token),
+            &token,
+        ),
+        (client.proof("p4", "POST", URL, &other_token), &token),
+        // Signed by a key the token is not bound to.
+        (other.proof("p5", "POST", URL, &token), &token),
+        (stale, &token),
+        (
+            client.proof_with(
+                json!({ "typ": "JWT" }),
+                proof_claims("p7", "POST", URL, &token),
+            ),
+            &token,
+        ),
+        // A valid proof for a token that is not DPoP-bound.
+        (client.proof("p8", "POST", URL, &bearer), &bearer),
+    ];
+    for (proof, token) in rejected {
+        assert_invalid_proof(auth.user_data_from_dpop_token(token, &proof, "POST", URL, None));
+    }
+}
+
+#[tokio::test]
+async fn verifies_proofs_asynchronously() {
+    let auth = common::auth().with_dpop(DpopConfig {
+        algorithms: Some(vec!["ES256".into()]),
+        ..Default::default()
+    });
+    let client = Client::new(5);
+    let token = client.token();
+
+    let proof = client.proof("p1", "GET", URL, &token);
+    let user = auth
+        .user_data_from_dpop_token_async(&token, &proof, "GET", URL, Some("access"))
+        .await
+        .unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+
+    // The proof key must be public.
+    let mut private_jwk = client.jwk.clone();
+    private_jwk["d"] = URL_SAFE_NO_PAD.encode([5u8; 32]).into();
+    let proof = client.proof_with(
+        json!({ "jwk": private_jwk }),
+        proof_claims("p2", "GET", URL, &token),
+    );
+    assert_invalid_proof(
+        auth.user_data_from_dpop_token_async(&token, &proof, "GET", URL, None)
+            .await,
+    );
+}
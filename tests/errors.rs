@@ -0,0 +1,99 @@
+mod common;
+
+use serde_json::json;
+use usso::config::AuthConfig;
+use usso::core::{decode_token, UssoAuth};
+use usso::exceptions::USSOError;
+use usso::schemas::{JWTConfig, Jwk, Jwks};
+
+fn inline_config() -> JWTConfig {
+    let keys: Jwks = serde_json::from_str(&common::jwks_json()).unwrap();
+    JWTConfig::new(None, Some(keys))
+}
+
+#[test]
+fn verification_failures_are_distinguished() {
+    let config = inline_config();
+    let claims = common::access_claims();
+
+    let err = config.decode("not-a-jwt").unwrap_err();
+    assert!(matches!(err, USSOError::MalformedToken(_)));
+    assert_eq!(err.error_code(), "malformed_token");
+
+    let token = common::sign_with_header(json!({"kid": "rotated-away"}), &claims);
+    let err = config.decode(&token).unwrap_err();
+    assert!(matches!(err, USSOError::UnknownKey(ref kid) if kid == "rotated-away"));
+    assert_eq!(err.error_code(), "unknown_kid");
+
+    // Graft the signature of one token onto the payload of another.
+    let token: Vec<String> = common::sign(&claims).split('.').map(String::from).collect();
+    let forged = common::sign(&json!({"sub": "admin"}));
+    let forged_payload = forged.split('.').nth(1).unwrap();
+    let tampered = format!("{}.{}.{}", token[0], forged_payload, token[2]);
+    let err = config.decode(&tampered).unwrap_err();
+    assert!(matches!(err, USSOError::InvalidSignature), "{err:?}");
+
+    let expired = common::sign(&json!({"sub": "u_1", "exp": common::now() - 600}));
+    let err = config.decode(&expired).unwrap_err();
+    assert!(matches!(err, USSOError::ExpiredToken));
+    assert_eq!(err.claim(), Some("exp"));
+
+    let immature = common::sign(&json!({"sub": "u_1", "nbf": common::now() + 600}));
+    let err = config.decode(&immature).unwrap_err();
+    assert!(matches!(err, USSOError::InvalidClaim(_)));
+    assert_eq!(err.claim(), Some("nbf"));
+    assert_eq!(err.status_code(), 401);
+}
+
+#[test]
+fn unsupported_algorithm_is_reported() {
+    let mut jwk = common::jwk();
+    jwk.as_object_mut().unwrap().remove("alg");
+    let key: Jwk = serde_json::from_value(jwk).unwrap();
+
+    let token = common::sign_with_header(json!({"alg": "XS256"}), &common::access_claims());
+    let err = decode_token(&key, &token).unwrap_err();
+    assert!(matches!(err, USSOError::UnsupportedAlgorithm(ref alg) if alg == "XS256"));
+}
+
+#[test]
+fn unreachable_jwks_maps_to_service_unavailable() {
+    let mut server = mockito::Server::new();
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(500)
+        .create();
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url))), None);
+    let token = common::sign(&common::access_claims());
+    let err = auth
+        .user_data_from_token(&token, Some("access"))
+        .unwrap_err();
+    assert!(matches!(err, USSOError::JwksUnavailable(_)), "{err:?}");
+    assert_eq!(err.status_code(), 503);
+    assert_eq!(err.error_code(), "jwks_unavailable");
+}
+
+#[test]
+fn usso_auth_reports_why_a_token_was_rejected() {
+    let mut server = mockito::Server::new();
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .create();
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url))), None);
+
+    let expired = common::sign(&json!({"sub": "u_1", "exp": common::now() - 600}));
+    let err = auth.user_data_from_token(&expired, None).unwrap_err();
+    assert!(matches!(err, USSOError::ExpiredToken), "{err:?}");
+
+    let refresh = common::sign(&json!({"sub": "u_1", "token_type": "refresh"}));
+    let err = auth
+        .user_data_from_token(&refresh, Some("access"))
+        .unwrap_err();
+    assert!(matches!(err, USSOError::InvalidTokenType), "{err:?}");
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use serde_json::json;
+use usso::config::AuthConfig;
+use usso::core::UssoAuth;
+use usso::exceptions::{ClaimError, USSOError};
+use usso::issuers::IssuerRegistry;
+
+const ACME: &str = "https://acme.usso.io";
+const GLOBEX: &str = "https://globex.usso.io";
+
+fn claims(iss: &str, aud: &str) -> serde_json::Value {
+    let mut claims = common::access_claims();
+    claims["iss"] = iss.into();
+    claims["aud"] = aud.into();
+    claims
+}
+
+#[test]
+fn routes_tokens_by_issuer_with_per_tenant_audiences() {
+    let mut server = mockito::Server::new();
+    let acme_keys = server
+        .mock("GET", "/acme/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .expect(1)
+        .create();
+    let globex_keys = server
+        .mock("GET", "/globex/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .expect(0)
+        .create();
+
+    let mut acme = AuthConfig::new(Some(format!("{}/acme/jwks.json", server.url())));
+    acme.validation.audiences = Some(vec!["billing".into()]);
+    let mut globex = AuthConfig::new(Some(format!("{}/globex/jwks.json", server.url())));
+    globex.validation.audiences = Some(vec!["shipping".into()]);
+    let auth = UssoAuth::new(None, None).with_issuers(
+        IssuerRegistry::new()
+            .with_issuer(ACME, acme)
+            .with_issuer(GLOBEX, globex),
+    );
+
+    let token = common::sign(&claims(ACME, "billing"));
+    let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+    assert_eq!(user.iss.as_deref(), Some(ACME));
+
+    // Acme tokens are checked against Acme's audiences only.
+    let token = common::sign(&claims(ACME, "shipping"));
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(
+        matches!(err, USSOError::InvalidClaim(ClaimError::InvalidAudience)),
+        "{err:?}"
+    );
+
+    let token = common::sign(&claims("https://initech.usso.io", "billing"));
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(
+        matches!(err, USSOError::UnknownIssuer(ref iss) if iss == "https://initech.usso.io"),
+        "{err:?}"
+    );
+
+    acme_keys.assert();
+    globex_keys.assert();
+}
+
+#[test]
+fn falls_back_to_the_header_host() {
+    let mut server = mockito::Server::new();
+    server
+        .mock("GET", "/globex/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .create();
+
+    let mut globex = AuthConfig::new(Some(format!("{}/globex/jwks.json", server.url())));
+    globex.validation.issuers = Some(vec![GLOBEX.into()]);
+    let registry = IssuerRegistry::new().with_host("globex.example.com", globex);
+    assert!(registry.get_by_host("GLOBEX.example.com").is_some());
+    let auth = UssoAuth::new(None, None).with_issuers(registry);
+
+    let token = common::sign_with_header(
+        json!({"host": "globex.example.com"}),
+        &claims(GLOBEX, "shipping"),
+    );
+    assert!(auth.user_data_from_token(&token, Some("access")).is_ok());
+
+    // The host only selects the config; the issuer is still enforced.
+    let token = common::sign_with_header(
+        json!({"host": "globex.example.com"}),
+        &claims(ACME, "shipping"),
+    );
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(
+        matches!(err, USSOError::InvalidClaim(ClaimError::InvalidIssuer(_))),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn registering_an_issuer_again_replaces_its_config() {
+    let mut first = AuthConfig::from_keys(serde_json::from_str(&common::jwks_json()).unwrap());
+    first.validation.audiences = Some(vec!["billing".into()]);
+    let mut second = first.clone();
+    second.validation.audiences = Some(vec!["shipping".into()]);
+
+    let registry = IssuerRegistry::new()
+        .with_issuer(ACME, first.clone())
+        .with_issuer(ACME, second)
+        .with_host("acme.example.com", first.clone())
+        .with_host("ACME.example.com", first);
+    assert_eq!(registry.configs().count(), 2);
+    let auth = UssoAuth::new(None, None).with_issuers(registry);
+
+    assert!(auth
+        .user_data_from_token(&common::sign(&claims(ACME, "shipping")), None)
+        .is_ok());
+    assert!(auth
+        .user_data_from_token(&common::sign(&claims(ACME, "billing")), None)
+        .is_err());
+}
+
+#[test]
+fn issuers_match_exactly() {
+    let keys = || AuthConfig::from_keys(serde_json::from_str(&common::jwks_json()).unwrap());
+    let with_slash = format!("{ACME}/");
+    let registry = IssuerRegistry::new().with_issuer(with_slash.as_str(), keys());
+    assert!(registry.get(&with_slash).is_some());
+    assert!(registry.get(ACME).is_none());
+    let auth = UssoAuth::new(None, None).with_issuers(registry);
+
+    let token = common::sign(&claims(&with_slash, "billing"));
+    assert!(auth.user_data_from_token(&token, Some("access")).is_ok());
+
+    // Without the trailing slash the token is routed nowhere, rather than to
+    // a config whose `iss` check would then reject it.
+    let token = common::sign(&claims(ACME, "billing"));
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(
+        matches!(err, USSOError::UnknownIssuer(ref iss) if iss == ACME),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rsa::{Oaep, RsaPublicKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use usso::config::AuthConfig;
+use usso::core::{decode_token, UssoAuth};
+use usso::exceptions::USSOError;
+use usso::jwe::{decrypt_to_jws, DecryptionKey};
+use usso::schemas::Jwk;
+
+const RSA_PEM: &str = include_str!("fixtures/jwe-rsa.pem");
+const P256_PEM: &str = include_str!("fixtures/jwe-p256.pem");
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Seal `plaintext` with A256GCM under `cek` and assemble the compact JWE.
+fn seal(header: Value, encrypted_key: &[u8], cek: &[u8], plaintext: &str) -> String {
+    let protected = URL_SAFE_NO_PAD.encode(header.to_string());
+    let iv = random_bytes::<12>();
+    let cipher = Aes256Gcm::new_from_slice(cek).unwrap();
+    let sealed = cipher
+        .encrypt(
+            Nonce::from_slice(&iv),
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: protected.as_bytes(),
+            },
+        )
+        .unwrap();
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - 16);
+    [
+        protected,
+        URL_SAFE_NO_PAD.encode(encrypted_key),
+        URL_SAFE_NO_PAD.encode(iv),
+        URL_SAFE_NO_PAD.encode(ciphertext),
+        URL_SAFE_NO_PAD.encode(tag),
+    ]
+    .join(".")
+}
+
+fn encrypt_rsa_oaep_256(public: &RsaPublicKey, plaintext: &str) -> String {
+    let cek = random_bytes::<32>();
+    let encrypted_key = public
+        .encrypt(&mut OsRng, Oaep::new::<Sha256>(), &cek)
+        .unwrap();
+    let header = json!({"alg": "RSA-OAEP-256", "enc": "A256GCM", "cty": "JWT"});
+    seal(header, &encrypted_key, &cek, plaintext)
+}
+
+fn concat_kdf(z: &[u8], algorithm_id: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(z);
+    hasher.update((algorithm_id.len() as u32).to_be_bytes());
+    hasher.update(algorithm_id);
+    hasher.update(0u32.to_be_bytes());
+    hasher.update(0u32.to_be_bytes());
+    hasher.update(256u32.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn encrypt_ecdh_es(recipient: &p256::PublicKey, alg: &str, plaintext: &str) -> String {
+    let ephemeral = p256::ecdh::EphemeralSecret::random(&mut OsRng);
+    let point = ephemeral.public_key().to_encoded_point(false);
+    let shared = ephemeral.diffie_hellman(recipient);
+    let header = json!({
+        "alg": alg,
+        "enc": "A256GCM",
+        "cty": "JWT",
+        "epk": {
+            "kty": "EC",
+            "crv": "P-256",
+            "x": URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+            "y": URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+        },
+    });
+    if alg == "ECDH-ES" {
+        let cek = concat_kdf(shared.raw_secret_bytes(), "A256GCM");
+        seal(header, &[], &cek, plaintext)
+    } else {
+        let kek = aes_kw::KekAes256::from(concat_kdf(shared.raw_secret_bytes(), alg));
+        let cek = random_bytes::<32>();
+        let mut wrapped = [0u8; 40];
+        kek.wrap(&cek, &mut wrapped).unwrap();
+        seal(header, &wrapped, &cek, plaintext)
+    }
+}
+
+fn verification_key() -> Jwk {
+    serde_json::from_value(common::jwk()).unwrap()
+}
+
+#[test]
+fn decrypts_rsa_oaep_256_tokens_in_usso_auth() {
+    let mut server = mockito::Server::new();
+    let jwk_url = format!("{}/.well-known/jwks.json", server.url());
+    server
+        .mock("GET", "/.well-known/jwks.json")
+        .with_status(200)
+        .with_body(common::jwks_json())
+        .create();
+
+    let key = DecryptionKey::from_pem(RSA_PEM).unwrap();
+    let public =
+        RsaPublicKey::from(&rsa::pkcs8::DecodePrivateKey::from_pkcs8_pem(RSA_PEM).unwrap());
+    let jwe = encrypt_rsa_oaep_256(&public, &common::sign(&common::access_claims()));
+
+    let auth = UssoAuth::new(Some(AuthConfig::new(Some(jwk_url))), None);
+    let err = auth.user_data_from_token(&jwe, Some("access")).unwrap_err();
+    assert!(matches!(err, USSOError::DecryptionFailed(_)), "{err:?}");
+
+    let auth = auth.with_decryption_keys(vec![key]);
+    let user = auth.user_data_from_token(&jwe, Some("access")).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+}
+
+#[test]
+fn decrypts_ecdh_es_tokens() {
+    let key = DecryptionKey::from_pem(P256_PEM).unwrap();
+    let secret = p256::SecretKey::from_sec1_pem(P256_PEM).unwrap();
+    let jws = common::sign(&common::access_claims());
+
+    for alg in ["ECDH-ES", "ECDH-ES+A256KW"] {
+        let jwe = encrypt_ecdh_es(&secret.public_key(), alg, &jws);
+        let inner = decrypt_to_jws(&jwe, std::slice::from_ref(&key)).unwrap();
+        assert_eq!(inner, jws, "{alg}");
+        let user = decode_token(&verification_key(), &inner).unwrap();
+        assert_eq!(user.sub.as_deref(), Some("u_1"));
+    }
+}
+
+#[test]
+fn rejects_tampered_or_unsupported_tokens() {
+    let secret = p256::SecretKey::from_sec1_pem(P256_PEM).unwrap();
+    let key = DecryptionKey::p256(secret.clone()).with_kid("enc-1");
+    let jws = common::sign(&common::access_claims());
+    let jwe = encrypt_ecdh_es(&secret.public_key(), "ECDH-ES", &jws);
+
+    let mut parts: Vec<String> = jwe.split('.').map(String::from).collect();
+    parts[3] = URL_SAFE_NO_PAD.encode(b"forged ciphertext");
+    let err = decrypt_to_jws(&parts.join("."), std::slice::from_ref(&key)).unwrap_err();
+    assert!(matches!(err, USSOError::DecryptionFailed(_)), "{err:?}");
+
+    let other = DecryptionKey::p256(p256::SecretKey::random(&mut OsRng));
+    let err = decrypt_to_jws(&jwe, &[other]).unwrap_err();
+    assert!(matches!(err, USSOError::DecryptionFailed(_)), "{err:?}");
+
+    let rsa_only = DecryptionKey::from_pem(RSA_PEM).unwrap().with_kid("enc-1");
+    let err = decrypt_to_jws(&jwe, std::slice::from_ref(&rsa_only)).unwrap_err();
+    assert!(matches!(err, USSOError::DecryptionFailed(_)), "{err:?}");
+
+    // A `kid` naming no known key is reported as such.
+    let public =
+        RsaPublicKey::from(&rsa::pkcs8::DecodePrivateKey::from_pkcs8_pem(RSA_PEM).unwrap());
+    let cek = random_bytes::<32>();
+    let encrypted_key = public
+        .encrypt(&mut OsRng, Oaep::new::<Sha256>(), &cek)
+        .unwrap();
+    let header = json!({"alg": "RSA-OAEP-256", "enc": "A256GCM", "kid": "enc-2"});
+    let jwe_for_other_kid = seal(header, &encrypted_key, &cek, &jws);
+    let err = decrypt_to_jws(&jwe_for_other_kid, &[rsa_only]).unwrap_err();
+    assert!(
+        matches!(err, USSOError::UnknownKey(ref kid) if kid == "enc-2"),
+        "{err:?}"
+    );
+
+    let header = json!({"alg": "dir", "enc": "A256GCM"});
+    let jwe = seal(header, &[], &random_bytes::<32>(), &jws);
+    let err = decrypt_to_jws(&jwe, &[key]).unwrap_err();
+    assert!(matches!(err, USSOError::UnsupportedAlgorithm(ref alg) if alg == "dir"));
+}
@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use usso::jwks::{get_jwk_keys, init_jwks_sync};
+    use std::time::Duration;
+
+    use usso::exceptions::JwksError;
+    use usso::jwks::cache::{JwksCache, JwksCacheConfig};
+    use usso::jwks::{get_jwk_keys, get_jwk_keys_for, init_jwks_sync};
 
     fn mock_jwks_json() -> &'static str {
         r#"{
@@ -54,4 +58,335 @@ mod tests {
 
         mock.assert();
     }
+
+    #[test]
+    fn init_jwks_multiple_urls() {
+        let mut server = mockito::Server::new();
+        let url = server.url();
+
+        let first = server
+            .mock("GET", "/tenant-a/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json())
+            .expect(2)
+            .create();
+        let second = server
+            .mock("GET", "/tenant-b/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json().replace("RS256", "RS384"))
+            .create();
+
+        let url_a = format!("{}/tenant-a/jwks.json", url);
+        let url_b = format!("{}/tenant-b/jwks.json", url);
+        init_jwks_sync(&url_a).expect("Can't init first JWKS");
+        init_jwks_sync(&url_b).expect("Can't init second JWKS");
+        // Re-initializing an already cached URL replaces its key set.
+        init_jwks_sync(&url_a).expect("Can't re-init first JWKS");
+
+        let keys_a = get_jwk_keys_for(&url_a).unwrap();
+        let keys_b = get_jwk_keys_for(&url_b).unwrap();
+        assert_eq!(keys_a.keys[0].alg.as_deref(), Some("RS256"));
+        assert_eq!(keys_b.keys[0].alg.as_deref(), Some("RS384"));
+
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn cache_refetches_after_ttl_and_invalidate() {
+        let mut server = mockito::Server::new();
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let mock = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json())
+            .expect(3)
+            .create();
+
+        let cache = JwksCache::new(JwksCacheConfig {
+            ttl: Duration::from_millis(50),
+            min_refetch_interval: Duration::ZERO,
+            ..Default::default()
+        });
+        assert!(cache.get(&jwk_url).is_none());
+
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+        assert!(cache.get(&jwk_url).is_some());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get(&jwk_url).is_none());
+        assert!(cache.peek(&jwk_url).is_some());
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+
+        assert!(cache.invalidate(&jwk_url));
+        assert!(cache.peek(&jwk_url).is_none());
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+
+        mock.assert();
+    }
+
+    #[test]
+    fn expired_keys_are_served_when_refetch_fails() {
+        let mut server = mockito::Server::new();
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let healthy = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json())
+            .create();
+
+        let cache = JwksCache::new(JwksCacheConfig {
+            ttl: Duration::from_millis(50),
+            ..Default::default()
+        });
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+        healthy.remove();
+
+        let _down = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(500)
+            .create();
+        std::thread::sleep(Duration::from_millis(60));
+
+        let keys = cache.get_or_fetch_sync(&jwk_url).unwrap();
+        assert_eq!(keys.keys[0].kid, "test-key-1");
+        assert!(cache.find_key_sync(&jwk_url, "test-key-1").is_ok());
+
+        // Without a last-known-good key set the failure is reported.
+        assert!(cache.invalidate(&jwk_url));
+        assert!(cache.get_or_fetch_sync(&jwk_url).is_err());
+    }
+
+    #[test]
+    fn expired_entry_refetch_is_rate_limited() {
+        let mut server = mockito::Server::new();
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let healthy = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json())
+            .create();
+
+        let cache = JwksCache::new(JwksCacheConfig {
+            ttl: Duration::from_millis(50),
+            min_refetch_interval: Duration::from_millis(300),
+            ..Default::default()
+        });
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+        healthy.assert();
+        healthy.remove();
+
+        let down = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(500)
+            .expect(1)
+            .create();
+
+        // Expired, but fetched moments ago: the stale keys are served as is.
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get(&jwk_url).is_none());
+        assert!(cache.get_or_fetch_sync(&jwk_url).is_ok());
+
+        // One refetch once the interval has passed; it fails and the
+        // following lookups keep serving the stale keys without retrying.
+        std::thread::sleep(Duration::from_millis(300));
+        for _ in 0..5 {
+            let keys = cache.get_or_fetch_sync(&jwk_url).unwrap();
+            assert_eq!(keys.keys[0].kid, "test-key-1");
+        }
+
+        down.assert();
+    }
+
+    #[test]
+    fn unknown_kid_refetch_is_rate_limited() {
+        let mut server = mockito::Server::new();
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let before_rotation = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json())
+            .create();
+
+        let cache = JwksCache::new(JwksCacheConfig {
+            min_refetch_interval: Duration::from_millis(500),
+            ..Default::default()
+        });
+        assert!(cache.find_key_sync(&jwk_url, "test-key-1").is_ok());
+        before_rotation.assert();
+        before_rotation.remove();
+
+        let after_rotation = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(mock_jwks_json().replace("test-key-1", "test-key-2"))
+            .expect(1)
+            .create();
+
+        // The key set was fetched moments ago, so the unknown kid is rejected
+        // without contacting the server.
+        assert!(matches!(
+            cache.find_key_sync(&jwk_url, "test-key-2"),
+            Err(JwksError::UnknownKid(_))
+        ));
+
+        std::thread::sleep(Duration::from_millis(550));
+        let key = cache.find_key_sync(&jwk_url, "test-key-2").unwrap();
+        assert_eq!(key.kid, "test-key-2");
+
+        // A forged kid right after the refetch does not hit the server again.
+        assert!(matches!(
+            cache.find_key_sync(&jwk_url, "forged"),
+            Err(JwksError::UnknownKid(_))
+        ));
+
+        after_rotation.assert();
+    }
+
+    #[test]
+    fn cache_honors_http_caching_headers() {
+        let mut server = mockito::Server::new();
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let initial = server
+            .mock("GET", "/website/jwks.json")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("cache-control", "public, max-age=120")
+            .with_header("etag", "\"v1\"")
+            .with_body(mock_jwks_json())
+            .expect(1)
+            .create();
+        let revalidation = server
+            .mock("GET", "/website/jwks.json")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .with_header("cache-control", "max-age=5")
+            .expect(1)
+            .create();
+
+        let cache = JwksCache::new(JwksCacheConfig {
+            min_ttl: Duration::from_secs(10),
+            max_ttl: Duration::from_secs(60),
+            ..Default::default()
+        });
+
+        cache.get_or_fetch_sync(&jwk_url).unwrap();
+        let entry = cache.entry(&jwk_url).unwrap();
+        assert_eq!(entry.http.max_age, Some(Duration::from_secs(120)));
+        assert_eq!(entry.http.etag.as_deref(), Some("\"v1\""));
+        // max-age=120 is clamped to the 60s ceiling.
+        assert_eq!(entry.expires_at - entry.fetched_at, Duration::from_secs(60));
+
+        let keys = cache.refresh_sync(&jwk_url).unwrap();
+        assert_eq!(keys.keys[0].kid, "test-key-1");
+        let entry = cache.entry(&jwk_url).unwrap();
+        // The validator survives the 304 and max-age=5 is raised to the 10s floor.
+        assert_eq!(entry.http.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(entry.expires_at - entry.fetched_at, Duration::from_secs(10));
+
+        initial.assert();
+        revalidation.assert();
+    }
+}
+
+mod refresher {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use usso::jwks::cache::{JwksCache, JwksCacheConfig};
+    use usso::jwks::refresher::{JwksRefresher, RefresherConfig};
+    use usso::schemas::Jwks;
+
+    async fn wait_until(mut condition: impl FnMut() -> bool) {
+        for _ in 0..100 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("condition not met in time");
+    }
+
+    #[tokio::test]
+    async fn cached_keys_outlive_the_refresh_interval() {
+        let mut server = mockito::Server::new_async().await;
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let _healthy = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(r#"{"keys": [{"kid": "k1", "kty": "OKP", "use": "sig", "crv": "Ed25519", "x": "AA"}]}"#)
+            .create_async()
+            .await;
+
+        let cache = Arc::new(JwksCache::new(JwksCacheConfig {
+            ttl: Duration::from_millis(10),
+            ..Default::default()
+        }));
+        let refresher = JwksRefresher::spawn_with_cache(
+            &jwk_url,
+            cache.clone(),
+            RefresherConfig {
+                interval: Duration::from_secs(60),
+                retry_interval: Duration::from_secs(60),
+                jitter: Duration::from_secs(5),
+            },
+        );
+        wait_until(|| refresher.last_successful_refresh().is_some()).await;
+
+        // The cache TTL is shorter than the refresh interval, so the entry
+        // lives until the next scheduled refresh instead.
+        let entry = cache.entry(&jwk_url).unwrap();
+        assert_eq!(entry.expires_at - entry.fetched_at, Duration::from_secs(65));
+
+        refresher.shutdown().await;
+        cache.insert(&jwk_url, Jwks { keys: Vec::new() });
+        let entry = cache.entry(&jwk_url).unwrap();
+        assert_eq!(entry.expires_at - entry.fetched_at, Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn keeps_last_known_good_keys_when_server_fails() {
+        let mut server = mockito::Server::new_async().await;
+        let jwk_url = format!("{}/website/jwks.json", server.url());
+
+        let healthy = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(200)
+            .with_body(r#"{"keys": [{"kid": "k1", "kty": "OKP", "use": "sig", "crv": "Ed25519", "x": "AA"}]}"#)
+            .create_async()
+            .await;
+
+        let refresher = JwksRefresher::spawn(
+            &jwk_url,
+            RefresherConfig {
+                interval: Duration::from_millis(30),
+                retry_interval: Duration::from_millis(30),
+                jitter: Duration::ZERO,
+            },
+        );
+        wait_until(|| refresher.last_successful_refresh().is_some()).await;
+        assert_eq!(refresher.find_key("k1").unwrap().kid, "k1");
+        assert!(!refresher.is_stale(Duration::from_secs(60)));
+
+        healthy.remove_async().await;
+        let _down = server
+            .mock("GET", "/website/jwks.json")
+            .with_status(503)
+            .create_async()
+            .await;
+        wait_until(|| refresher.last_error().is_some()).await;
+
+        assert_eq!(refresher.find_key("k1").unwrap().kid, "k1");
+        assert!(refresher.find_key("unknown").is_err());
+        assert!(refresher.is_stale(Duration::ZERO));
+
+        refresher.shutdown().await;
+    }
 }
@@ -0,0 +1,119 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::EncodePublicKey;
+use serde_json::json;
+use usso::config::AuthConfig;
+use usso::core::UssoAuth;
+use usso::exceptions::USSOError;
+use usso::jwks::local::{load_jwks_env, load_jwks_file, load_pem_dir};
+
+/// A scratch directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("usso-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn validates_tokens_with_a_local_jwks_file() {
+    // Real-world key sets often omit `use`.
+    let mut jwk = common::jwk();
+    jwk.as_object_mut().unwrap().remove("use");
+    let dir = TempDir::new("jwks-file");
+    let path = dir.path().join("jwks.json");
+    std::fs::write(&path, json!({ "keys": [jwk] }).to_string()).unwrap();
+
+    let keys = load_jwks_file(&path).unwrap();
+    assert_eq!(keys.keys[0].r#use, None);
+
+    let auth = UssoAuth::new(Some(AuthConfig::from_keys(keys)), None);
+    let token = common::sign(&common::access_claims());
+    let user = auth.user_data_from_token(&token, Some("access")).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+
+    let token = common::sign_with_header(json!({"kid": "unknown"}), &common::access_claims());
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::UnknownKey(_)), "{err:?}");
+}
+
+#[test]
+fn loads_a_directory_of_pem_public_keys() {
+    let temp = TempDir::new("pem-dir");
+    let dir = temp.path();
+    let ed25519 = common::signing_key()
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    std::fs::write(dir.join(format!("{}.pem", common::TEST_KID)), ed25519).unwrap();
+    let p256 = p256::SecretKey::from_sec1_pem(include_str!("fixtures/jwe-p256.pem"))
+        .unwrap()
+        .public_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    std::fs::write(dir.join("ec-key.pem"), p256).unwrap();
+    let rsa = rsa::RsaPublicKey::from(
+        &<rsa::RsaPrivateKey as rsa::pkcs8::DecodePrivateKey>::from_pkcs8_pem(include_str!(
+            "fixtures/jwe-rsa.pem"
+        ))
+        .unwrap(),
+    )
+    .to_public_key_pem(LineEnding::LF)
+    .unwrap();
+    std::fs::write(dir.join("rsa-key.pem"), rsa).unwrap();
+    std::fs::write(dir.join("README"), "not a key").unwrap();
+
+    let keys = load_pem_dir(dir).unwrap();
+    let summary: Vec<_> = keys
+        .keys
+        .iter()
+        .map(|k| (k.kid.as_str(), k.kty.as_str(), k.alg.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("ec-key", "EC", Some("ES256")),
+            ("rsa-key", "RSA", None),
+            (common::TEST_KID, "OKP", Some("EdDSA")),
+        ]
+    );
+
+    let auth = UssoAuth::new(Some(AuthConfig::from_keys(keys)), None);
+    let token = common::sign(&common::access_claims());
+    assert!(auth.user_data_from_token(&token, Some("access")).is_ok());
+}
+
+#[test]
+fn loads_jwks_from_the_environment_and_rejects_encryption_keys() {
+    let mut jwk = common::jwk();
+    jwk["use"] = "enc".into();
+    // A variable unique to this process, removed before any assertion.
+    let var = format!("USSO_TEST_JWKS_{}", std::process::id());
+    std::env::set_var(&var, json!({ "keys": [jwk] }).to_string());
+    let keys = load_jwks_env(&var);
+    std::env::remove_var(&var);
+
+    let keys = keys.unwrap();
+    assert!(load_jwks_env(&var).is_err());
+
+    let auth = UssoAuth::new(Some(AuthConfig::from_keys(keys)), None);
+    let token = common::sign(&common::access_claims());
+    let err = auth.user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::KeyMismatch(_)), "{err:?}");
+}
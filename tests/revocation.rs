@@ -0,0 +1,76 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use usso::exceptions::USSOError;
+use usso::jwks::refresher::RefresherConfig;
+use usso::revocation::{InMemoryRevocationList, RevocationPoller};
+
+fn token(jti: &str, session_id: &str) -> String {
+    let mut claims = common::access_claims();
+    claims["jti"] = jti.into();
+    claims["session_id"] = session_id.into();
+    common::sign(&claims)
+}
+
+#[test]
+fn rejects_tokens_revoked_in_memory() {
+    let revoked = Arc::new(InMemoryRevocationList::new());
+    let auth = common::auth().with_revocation_checker(revoked.clone());
+    assert!(auth.user_data_from_token(&token("t1", "s1"), None).is_ok());
+
+    revoked.revoke_jti("t1", Duration::from_secs(60));
+    revoked.revoke_session("s2", Duration::from_secs(60));
+    revoked.revoke_jti("t3", Duration::ZERO);
+    assert_eq!(revoked.len(), 2);
+
+    for token in [token("t1", "s1"), token("t2", "s2")] {
+        let err = auth.user_data_from_token(&token, None).unwrap_err();
+        assert!(matches!(err, USSOError::RevokedToken), "{err:?}");
+        assert_eq!(err.error_code(), "revoked_token");
+    }
+    // Other tokens of the session, and expired revocations, are unaffected.
+    assert!(auth.user_data_from_token(&token("t4", "s1"), None).is_ok());
+    assert!(auth.user_data_from_token(&token("t3", "s3"), None).is_ok());
+}
+
+#[tokio::test]
+async fn polls_the_revocation_list_from_the_server() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/revocations")
+        .with_status(200)
+        .with_body(json!({ "jtis": ["t1"], "session_ids": ["s2"] }).to_string())
+        .create_async()
+        .await;
+
+    let poller = Arc::new(RevocationPoller::spawn(
+        &format!("{}/revocations", server.url()),
+        RefresherConfig::default(),
+    ));
+    for _ in 0..100 {
+        if poller.last_successful_refresh().is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(poller.list().jtis.contains("t1"), "{:?}", poller.last_error());
+
+    let auth = common::auth().with_revocation_checker(poller.clone());
+    for token in [token("t1", "s1"), token("t2", "s2")] {
+        let err = auth
+            .user_data_from_token_async(&token, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, USSOError::RevokedToken), "{err:?}");
+    }
+    assert!(auth
+        .user_data_from_token_async(&token("t3", "s3"), None)
+        .await
+        .is_ok());
+
+    drop(auth);
+    Arc::try_unwrap(poller).unwrap().shutdown().await;
+}
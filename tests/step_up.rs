@@ -0,0 +1,108 @@
+mod common;
+
+use std::time::Duration;
+
+use serde_json::json;
+use usso::exceptions::USSOError;
+use usso::schemas::UserData;
+use usso::step_up::StepUpRequirement;
+
+fn token(extra: serde_json::Value) -> String {
+    let mut claims = common::access_claims();
+    for (k, v) in extra.as_object().unwrap() {
+        claims[k] = v.clone();
+    }
+    common::sign(&claims)
+}
+
+#[test]
+fn enforces_amr_acr_and_authentication_age() {
+    let auth = common::auth();
+    let requirement = StepUpRequirement {
+        min_acr: Some("2".into()),
+        required_amr: vec!["otp".into()],
+        max_age: Some(Duration::from_secs(300)),
+        ..Default::default()
+    };
+
+    let auth_time = common::now() - 60;
+    let strong = token(json!({ "acr": "3", "amr": ["pwd", "otp"], "auth_time": auth_time }));
+    let user = auth
+        .user_data_from_token_with_step_up(&strong, Some("access"), &requirement)
+        .unwrap();
+    assert_eq!(user.auth_time, Some(auth_time));
+
+    let cases = [
+        (
+            json!({ "acr": "1", "amr": ["otp"] }),
+            "acr 2 or higher is required",
+        ),
+        (
+            json!({ "acr": "2", "amr": ["pwd"] }),
+            "authentication with otp is required",
+        ),
+        (
+            json!({ "acr": "2", "amr": ["otp"], "auth_time": common::now() - 3600 }),
+            "a more recent authentication is required",
+        ),
+    ];
+    for (claims, reason) in cases {
+        let err = auth
+            .user_data_from_token_with_step_up(&token(claims), None, &requirement)
+            .unwrap_err();
+        assert_eq!(err.error_code(), "insufficient_user_authentication");
+        assert_eq!(err.status_code(), 401);
+        let USSOError::StepUpRequired(challenge) = err else {
+            panic!("{err:?}");
+        };
+        assert_eq!(challenge.reason, reason);
+        assert_eq!(
+            challenge.www_authenticate(),
+            format!(
+                "Bearer error=\"insufficient_user_authentication\", \
+                 error_description=\"{reason}\", acr_values=\"2\", max_age=\"300\""
+            )
+        );
+    }
+
+    // Without `auth_time`, `iat` tells when the user authenticated.
+    let user: UserData = serde_json::from_value(json!({ "iat": common::now() - 3600 })).unwrap();
+    assert!(requirement.check(&user).is_err());
+
+    // Extreme `auth_time` values do not overflow.
+    let recent = |max_age| StepUpRequirement {
+        max_age: Some(max_age),
+        ..Default::default()
+    };
+    let user: UserData = serde_json::from_value(json!({ "auth_time": i64::MIN })).unwrap();
+    assert!(recent(Duration::from_secs(300)).check(&user).is_err());
+    assert!(recent(Duration::MAX).check(&user).is_ok());
+    let user: UserData = serde_json::from_value(json!({ "auth_time": i64::MAX })).unwrap();
+    assert!(recent(Duration::from_secs(300)).check(&user).is_ok());
+}
+
+#[tokio::test]
+async fn orders_named_levels() {
+    let auth = common::auth();
+    let requirement = StepUpRequirement {
+        min_signing_level: Some("advanced".into()),
+        acr_levels: Some(vec!["basic".into(), "advanced".into(), "qualified".into()]),
+        ..Default::default()
+    };
+
+    for (level, accepted) in [
+        ("qualified", true),
+        ("advanced", true),
+        ("basic", false),
+        ("custom", false),
+    ] {
+        let result = auth
+            .user_data_from_token_with_step_up_async(
+                &token(json!({ "signing_level": level })),
+                None,
+                &requirement,
+            )
+            .await;
+        assert_eq!(result.is_ok(), accepted, "{level}");
+    }
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use serde_json::json;
+use usso::config::TokenValidation;
+use usso::exceptions::{ClaimError, USSOError};
+use usso::schemas::{JWTConfig, Jwks, UserData};
+
+fn config(validation: TokenValidation) -> JWTConfig {
+    let keys: Jwks = serde_json::from_str(&common::jwks_json()).unwrap();
+    let mut config = JWTConfig::new(None, Some(keys));
+    config.validation = validation;
+    config
+}
+
+fn claims_with(extra: serde_json::Value) -> serde_json::Value {
+    let mut claims = common::access_claims();
+    for (k, v) in extra.as_object().unwrap() {
+        claims[k] = v.clone();
+    }
+    claims
+}
+
+#[test]
+fn time_claims_respect_leeway() {
+    let now = common::now();
+    let strict = config(TokenValidation {
+        leeway: 0,
+        ..Default::default()
+    });
+    let lenient = config(TokenValidation {
+        leeway: 120,
+        ..Default::default()
+    });
+
+    let expired = common::sign(&claims_with(json!({"exp": now - 60})));
+    assert!(matches!(strict.decode(&expired), Err(USSOError::ExpiredToken)));
+    assert!(lenient.decode(&expired).is_ok());
+
+    let immature = common::sign(&claims_with(json!({"nbf": now + 60})));
+    assert!(matches!(
+        strict.decode(&immature),
+        Err(USSOError::InvalidClaim(ClaimError::NotYetValid))
+    ));
+    assert!(lenient.decode(&immature).is_ok());
+
+    let future_iat = common::sign(&claims_with(json!({"iat": now + 600})));
+    assert!(matches!(
+        lenient.decode(&future_iat),
+        Err(USSOError::InvalidClaim(ClaimError::IssuedInFuture))
+    ));
+}
+
+#[test]
+fn extreme_time_claims_do_not_overflow() {
+    let strict = TokenValidation {
+        leeway: 120,
+        ..Default::default()
+    };
+    let lenient = TokenValidation {
+        leeway: u64::MAX,
+        ..Default::default()
+    };
+
+    for exp in [json!(i64::MAX), json!(1e300)] {
+        let claims = claims_with(json!({ "exp": exp }));
+        assert!(strict.validate(&claims).is_ok());
+        assert!(lenient.validate(&claims).is_ok());
+    }
+    let expired = claims_with(json!({ "exp": i64::MIN }));
+    assert!(matches!(strict.validate(&expired), Err(USSOError::ExpiredToken)));
+
+    let past = claims_with(json!({ "nbf": i64::MIN, "iat": i64::MIN }));
+    assert!(strict.validate(&past).is_ok());
+    assert!(lenient.validate(&past).is_ok());
+
+    let future = claims_with(json!({ "nbf": i64::MAX }));
+    assert!(matches!(
+        strict.validate(&future),
+        Err(USSOError::InvalidClaim(ClaimError::NotYetValid))
+    ));
+    let future = claims_with(json!({ "iat": i64::MAX }));
+    assert!(matches!(
+        strict.validate(&future),
+        Err(USSOError::InvalidClaim(ClaimError::IssuedInFuture))
+    ));
+}
+
+#[test]
+fn issuer_and_audience_are_enforced() {
+    let validation = config(TokenValidation {
+        issuers: Some(vec!["https://sso.usso.io".into()]),
+        audiences: Some(vec!["billing".into()]),
+        ..Default::default()
+    });
+
+    let ok = common::sign(&claims_with(json!({"aud": "billing"})));
+    assert!(validation.decode(&ok).is_ok());
+
+    let other_issuer = common::sign(&claims_with(json!({"aud": "billing", "iss": "https://evil"})));
+    let err = validation.decode(&other_issuer).unwrap_err();
+    assert_eq!(err.error_code(), "invalid_issuer");
+
+    let other_audience = common::sign(&claims_with(json!({"aud": "reports"})));
+    assert!(matches!(
+        validation.decode(&other_audience),
+        Err(USSOError::InvalidClaim(ClaimError::InvalidAudience))
+    ));
+
+    let no_audience = common::sign(&common::access_claims());
+    assert!(matches!(
+        validation.decode(&no_audience),
+        Err(USSOError::InvalidClaim(ClaimError::Missing(claim))) if claim == "aud"
+    ));
+}
+
+#[test]
+fn required_claims_must_be_present() {
+    let validation = config(TokenValidation {
+        required_claims: vec!["exp".into(), "jti".into()],
+        ..Default::default()
+    });
+
+    let without_jti = common::sign(&common::access_claims());
+    let err = validation.decode(&without_jti).unwrap_err();
+    assert!(matches!(&err, USSOError::InvalidClaim(e) if e.claim() == "jti"));
+    assert_eq!(err.status_code(), 401);
+
+    let with_jti = common::sign(&claims_with(json!({"jti": "abc"})));
+    assert!(validation.decode(&with_jti).is_ok());
+}
+
+#[test]
+fn algorithm_allow_list_is_enforced() {
+    let token = common::sign(&common::access_claims());
+
+    let eddsa_only = config(TokenValidation {
+        algorithms: Some(vec!["EdDSA".into()]),
+        ..Default::default()
+    });
+    assert!(eddsa_only.decode(&token).is_ok());
+
+    let rsa_only = config(TokenValidation {
+        algorithms: Some(vec!["RS256".into()]),
+        ..Default::default()
+    });
+    assert!(matches!(
+        rsa_only.decode(&token),
+        Err(USSOError::AlgorithmNotAllowed(alg)) if alg == "EdDSA"
+    ));
+}
+
+#[test]
+fn key_must_match_token_algorithm() {
+    let token = common::sign(&common::access_claims());
+    let with_key = |patch: serde_json::Value| {
+        let mut jwk = common::jwk();
+        for (k, v) in patch.as_object().unwrap() {
+            jwk[k] = v.clone();
+        }
+        let keys: Jwks = serde_json::from_value(json!({ "keys": [jwk] })).unwrap();
+        JWTConfig::new(None, Some(keys)).decode(&token)
+    };
+
+    assert!(with_key(json!({})).is_ok());
+    for patch in [
+        json!({"use": "enc"}),
+        json!({"alg": "RS256"}),
+        json!({"kty": "RSA"}),
+        json!({"crv": "X25519"}),
+    ] {
+        let err = with_key(patch).unwrap_err();
+        assert!(matches!(err, USSOError::KeyMismatch(_)), "{err:?}");
+        assert_eq!(err.error_code(), "key_mismatch");
+    }
+}
+
+#[test]
+fn decode_claims_into_custom_type() {
+    #[derive(serde::Deserialize)]
+    struct Tenant {
+        org: String,
+    }
+
+    let config = config(TokenValidation::default());
+    let token = common::sign(&claims_with(json!({"org": "acme"})));
+
+    let user: UserData<Tenant> = config.decode_claims(&token).unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+    assert_eq!(user.extra.org, "acme");
+
+    // The default `UserData` ignores unknown claims.
+    assert!(config.decode(&token).is_ok());
+
+    let expired = common::sign(&claims_with(json!({"org": "acme", "exp": common::now() - 600})));
+    assert!(matches!(
+        config.decode_claims::<UserData<Tenant>>(&expired),
+        Err(USSOError::ExpiredToken)
+    ));
+}
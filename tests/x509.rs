@@ -0,0 +1,105 @@
+mod common;
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use usso::config::AuthConfig;
+use usso::core::UssoAuth;
+use usso::exceptions::USSOError;
+use usso::jwks::x509::X509Validation;
+use usso::schemas::Jwks;
+use x509_cert::der::{DecodePem, Encode};
+use x509_cert::Certificate;
+
+const ROOT: &str = include_str!("fixtures/x5c-root.pem");
+const OTHER_ROOT: &str = include_str!("fixtures/x5c-other-root.pem");
+const INTERMEDIATE: &str = include_str!("fixtures/x5c-intermediate.pem");
+const LEAF: &str = include_str!("fixtures/x5c-leaf.pem");
+const EXPIRED_LEAF: &str = include_str!("fixtures/x5c-expired-leaf.pem");
+
+fn der(pem: &str) -> Vec<u8> {
+    Certificate::from_pem(pem).unwrap().to_der().unwrap()
+}
+
+/// A JWK for the test key carrying only its certificate chain.
+fn x5c_jwk(chain: &[&str]) -> Value {
+    json!({
+        "kid": common::TEST_KID,
+        "kty": "OKP",
+        "use": "sig",
+        "x5c": chain.iter().map(|pem| STANDARD.encode(der(pem))).collect::<Vec<_>>(),
+    })
+}
+
+fn auth(jwk: Value, x509: Option<X509Validation>) -> UssoAuth {
+    let keys: Jwks = serde_json::from_value(json!({ "keys": [jwk] })).unwrap();
+    let mut config = AuthConfig::from_keys(keys);
+    config.x509 = x509;
+    UssoAuth::new(Some(config), None)
+}
+
+#[test]
+fn derives_the_key_from_the_leaf_certificate() {
+    let mut jwk = x5c_jwk(&[LEAF, INTERMEDIATE]);
+    jwk["x5t#S256"] = URL_SAFE_NO_PAD.encode(Sha256::digest(der(LEAF))).into();
+    let token = common::sign(&common::access_claims());
+
+    let user = auth(jwk.clone(), None)
+        .user_data_from_token(&token, Some("access"))
+        .unwrap();
+    assert_eq!(user.sub.as_deref(), Some("u_1"));
+
+    // Raw parameters are allowed alongside the chain but must agree with it.
+    let mut with_material = common::jwk();
+    with_material["x5c"] = jwk["x5c"].clone();
+    assert!(auth(with_material.clone(), None)
+        .user_data_from_token(&token, None)
+        .is_ok());
+    with_material["x"] = URL_SAFE_NO_PAD.encode([1u8; 32]).into();
+    let err = auth(with_material, None)
+        .user_data_from_token(&token, None)
+        .unwrap_err();
+    assert!(matches!(err, USSOError::KeyMismatch(_)), "{err:?}");
+
+    jwk["x5t"] = "AAAA".into();
+    let err = auth(jwk, None).user_data_from_token(&token, None).unwrap_err();
+    assert!(matches!(err, USSOError::InvalidCertificate(_)), "{err:?}");
+}
+
+#[test]
+fn validates_the_chain_against_trust_anchors() {
+    let token = common::sign(&common::access_claims());
+    let trusted = || X509Validation::from_pem(ROOT).unwrap();
+
+    let auth_ok = auth(x5c_jwk(&[LEAF, INTERMEDIATE]), Some(trusted()));
+    assert!(auth_ok.user_data_from_token(&token, Some("access")).is_ok());
+    // The anchor may also be included at the end of the chain.
+    let auth_ok = auth(x5c_jwk(&[LEAF, INTERMEDIATE, ROOT]), Some(trusted()));
+    assert!(auth_ok.user_data_from_token(&token, Some("access")).is_ok());
+
+    let rejected = [
+        // Signed by a different root with the same name.
+        auth(
+            x5c_jwk(&[LEAF, INTERMEDIATE]),
+            Some(X509Validation::from_pem(OTHER_ROOT).unwrap()),
+        ),
+        // Missing intermediate.
+        auth(x5c_jwk(&[LEAF]), Some(trusted())),
+        // Chain out of order.
+        auth(x5c_jwk(&[INTERMEDIATE, LEAF]), Some(trusted())),
+        // Leaf expired in 2021.
+        auth(x5c_jwk(&[EXPIRED_LEAF, INTERMEDIATE]), Some(trusted())),
+        // No chain at all.
+        auth(common::jwk(), Some(trusted())),
+    ];
+    for auth in rejected {
+        let err = auth.user_data_from_token(&token, None).unwrap_err();
+        assert!(matches!(err, USSOError::InvalidCertificate(_)), "{err:?}");
+    }
+
+    let mut lenient = trusted();
+    lenient.check_validity = false;
+    let auth_ok = auth(x5c_jwk(&[EXPIRED_LEAF, INTERMEDIATE]), Some(lenient));
+    assert!(auth_ok.user_data_from_token(&token, None).is_ok());
+}